@@ -1,5 +1,6 @@
-use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
-use criterion::{criterion_group, criterion_main, Criterion};
+use blocking_delay_queue::{bench_support, BlockingDelayQueue, DelayItem};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 fn add_and_take_bench(c: &mut Criterion) {
@@ -19,5 +20,68 @@ fn offer_and_poll_bench(c: &mut Criterion) {
     c.bench_function("poll element", |b| b.iter(|| queue.poll(timeout)));
 }
 
-criterion_group!(benches, add_and_take_bench, offer_and_poll_bench);
+fn single_producer_bench(c: &mut Criterion) {
+    c.bench_function("single producer 1k", |b| {
+        b.iter_batched(
+            || bench_support::expired(1_000),
+            |items| {
+                bench_support::run_mpmc(Arc::new(BlockingDelayQueue::new_unbounded()), 1, 1, items)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn mpmc_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mpmc 10k");
+    for threads in [2, 4, 8].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), threads, |b, &n| {
+            b.iter_batched(
+                || bench_support::expired(10_000),
+                |items| {
+                    let queue = Arc::new(BlockingDelayQueue::new_with_capacity(256));
+                    bench_support::run_mpmc(queue, n, n, items)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bursty_expiry_bench(c: &mut Criterion) {
+    c.bench_function("bursty expiry 10 x 100", |b| {
+        b.iter_batched(
+            || bench_support::bursts(1_000, 100, Duration::from_micros(100)),
+            |items| {
+                bench_support::run_mpmc(Arc::new(BlockingDelayQueue::new_unbounded()), 1, 4, items)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn large_backlog_bench(c: &mut Criterion) {
+    // expired elements are added and taken on top of a backlog which never expires
+    let queue = BlockingDelayQueue::new_unbounded();
+    for e in bench_support::spread(100_000, Duration::from_secs(3600), 42) {
+        queue.add(DelayItem::new(e.data, e.delay + Duration::from_secs(3600)));
+    }
+    c.bench_function("add and take over 100k backlog", |b| {
+        b.iter(|| {
+            queue.add(DelayItem::new(0, Instant::now()));
+            queue.take()
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    add_and_take_bench,
+    offer_and_poll_bench,
+    single_producer_bench,
+    mpmc_bench,
+    bursty_expiry_bench,
+    large_backlog_bench
+);
 criterion_main!(benches);
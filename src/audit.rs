@@ -0,0 +1,107 @@
+use std::thread::{self, ThreadId};
+use std::time::SystemTime;
+
+use crate::listener::Outcome;
+
+/// The queue operation recorded in an [AuditRecord](crate::AuditRecord).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOperation {
+    /// An element was added to the queue.
+    Add,
+    /// An element was taken from the queue by a consumer.
+    Take,
+    /// An element was removed from the queue without being delivered, e.g. by
+    /// [clear](crate::BlockingDelayQueue::clear), a cancel scope, eviction or because it was stale.
+    Cancel,
+}
+
+impl From<Outcome> for AuditOperation {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Delivered => AuditOperation::Take,
+            Outcome::Cancelled => AuditOperation::Cancel,
+        }
+    }
+}
+
+/// An entry of the audit trail describing who did what to which element and when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// The name of the queue, see [name](crate::QueueBuilder::name).
+    pub queue: Option<String>,
+    /// The performed operation.
+    pub operation: AuditOperation,
+    /// The wall clock time the operation was performed at.
+    pub at: SystemTime,
+    /// The thread which performed the operation.
+    pub thread: ThreadId,
+    /// The element as rendered by the redaction callback.
+    pub payload: String,
+}
+
+/// A destination for the audit trail of a queue, see
+/// [audit](crate::QueueBuilder::audit).
+/// Records are passed to the sink after the queue lock is released, on the thread which
+/// performed the operation. Any `Fn(AuditRecord)` closure is a sink.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use blocking_delay_queue::{AuditRecord, AuditSink};
+/// struct Stdout;
+/// impl AuditSink for Stdout {
+///     fn record(&self, record: AuditRecord) {
+///         println!("{:?} {:?} {}", record.at, record.operation, record.payload);
+///     }
+/// }
+/// ```
+pub trait AuditSink {
+    /// Records an audited operation.
+    fn record(&self, record: AuditRecord);
+}
+
+impl<F> AuditSink for F
+where
+    F: Fn(AuditRecord),
+{
+    fn record(&self, record: AuditRecord) {
+        self(record)
+    }
+}
+
+pub(crate) struct Auditor<T> {
+    sink: Box<dyn AuditSink + Send + Sync>,
+    redact: Box<dyn Fn(&T) -> String + Send + Sync>,
+}
+
+impl<T> Auditor<T> {
+    pub(crate) fn new(
+        sink: impl AuditSink + Send + Sync + 'static,
+        redact: impl Fn(&T) -> String + Send + Sync + 'static,
+    ) -> Self {
+        Auditor {
+            sink: Box::new(sink),
+            redact: Box::new(redact),
+        }
+    }
+
+    // renders the record, which can happen before the sink is allowed to be called
+    pub(crate) fn prepare(
+        &self,
+        queue: Option<&str>,
+        operation: AuditOperation,
+        item: &T,
+    ) -> AuditRecord {
+        AuditRecord {
+            queue: queue.map(String::from),
+            operation,
+            at: SystemTime::now(),
+            thread: thread::current().id(),
+            payload: (self.redact)(item),
+        }
+    }
+
+    pub(crate) fn record(&self, record: AuditRecord) {
+        self.sink.record(record)
+    }
+}
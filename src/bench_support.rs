@@ -0,0 +1,144 @@
+//! Workload generators for benchmarking [BlockingDelayQueue](crate::BlockingDelayQueue) under
+//! different contention profiles, shared by the crate benchmarks and usable by downstream ones.
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+
+/// Returns 'n' elements which are all already expired.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use blocking_delay_queue::bench_support;
+/// assert_eq!(16, bench_support::expired(16).len());
+/// ```
+pub fn expired(n: usize) -> Vec<DelayItem<u64>> {
+    let now = Instant::now();
+    (0..n as u64).map(|i| DelayItem::new(i, now)).collect()
+}
+
+/// Returns 'n' elements with delays spread pseudo-randomly over the provided horizon from now.
+/// The same seed always produces the same spread.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::bench_support;
+/// let items = bench_support::spread(1000, Duration::from_secs(3600), 42);
+/// ```
+pub fn spread(n: usize, horizon: Duration, seed: u64) -> Vec<DelayItem<u64>> {
+    let now = Instant::now();
+    let horizon_nanos = horizon.as_nanos().max(1) as u64;
+    // xorshift keeps workloads reproducible without pulling in a rng dependency
+    let mut state = seed.max(1);
+    (0..n as u64)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            DelayItem::new(i, now + Duration::from_nanos(state % horizon_nanos))
+        })
+        .collect()
+}
+
+/// Returns 'n' elements grouped into bursts of 'burst_size' elements sharing the same delay,
+/// with consecutive bursts the provided spacing apart starting now.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::bench_support;
+/// let items = bench_support::bursts(1000, 100, Duration::from_millis(1));
+/// ```
+pub fn bursts(n: usize, burst_size: usize, spacing: Duration) -> Vec<DelayItem<u64>> {
+    let now = Instant::now();
+    let burst_size = burst_size.max(1);
+    (0..n)
+        .map(|i| DelayItem::new(i as u64, now + spacing * (i / burst_size) as u32))
+        .collect()
+}
+
+/// Adds the provided elements from 'producers' threads while 'consumers' threads take them,
+/// returning once every element was taken.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use blocking_delay_queue::{bench_support, BlockingDelayQueue};
+/// let queue = Arc::new(BlockingDelayQueue::new_with_capacity(64));
+/// bench_support::run_mpmc(queue, 2, 2, bench_support::expired(1000));
+/// ```
+pub fn run_mpmc(
+    queue: Arc<BlockingDelayQueue<DelayItem<u64>>>,
+    producers: usize,
+    consumers: usize,
+    items: Vec<DelayItem<u64>>,
+) {
+    let producers = producers.max(1);
+    let consumers = consumers.max(1);
+    let total = items.len();
+
+    let mut chunks: Vec<Vec<DelayItem<u64>>> = (0..producers).map(|_| Vec::new()).collect();
+    for (i, e) in items.into_iter().enumerate() {
+        chunks[i % producers].push(e);
+    }
+
+    let consumer_handles: Vec<_> = (0..consumers)
+        .map(|c| {
+            let queue = queue.clone();
+            // spread the remainder over the first consumers
+            let quota = total / consumers + usize::from(c < total % consumers);
+            thread::spawn(move || {
+                for _ in 0..quota {
+                    queue.take();
+                }
+            })
+        })
+        .collect();
+    let producer_handles: Vec<_> = chunks
+        .into_iter()
+        .map(|chunk| {
+            let queue = queue.clone();
+            thread::spawn(move || chunk.into_iter().for_each(|e| queue.add(e)))
+        })
+        .collect();
+
+    for h in producer_handles.into_iter().chain(consumer_handles) {
+        h.join().expect("Benchmark thread panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::bench_support;
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+
+    #[test]
+    fn should_drain_mpmc_workload() {
+        let queue = Arc::new(BlockingDelayQueue::new_with_capacity(8));
+        bench_support::run_mpmc(queue.clone(), 3, 2, bench_support::expired(101));
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_generate_reproducible_spread() {
+        let order = |mut items: Vec<crate::DelayItem<u64>>| -> Vec<u64> {
+            items.sort_by_key(|e| e.delay);
+            items.into_iter().map(|e| e.data).collect()
+        };
+        let horizon = Duration::from_secs(3600);
+        assert_eq!(
+            order(bench_support::spread(100, horizon, 7)),
+            order(bench_support::spread(100, horizon, 7))
+        );
+    }
+}
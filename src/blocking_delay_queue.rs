@@ -1,11 +1,98 @@
-use std::cmp::Reverse;
+use std::cmp::{Ordering, Reverse};
 use std::collections::BinaryHeap;
-use std::sync::{Condvar, Mutex, MutexGuard};
+use std::hint;
+use std::mem::MaybeUninit;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::mpsc::TrySendError;
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock, TryLockError};
 use std::time::{Duration, Instant};
 
-use crate::delay_item::Delayed;
+#[cfg(feature = "audit")]
+use crate::audit::{AuditOperation, Auditor};
+use crate::budgeted::Budgeted;
+use crate::builder::{LatenessCallback, PressureCallback, QueueBuilder};
+use crate::cancel_scope::CancelScope;
+use crate::config::QueueConfig;
+use crate::deadline_wait;
+#[cfg(feature = "debug-checks")]
+use crate::debug_checks;
+use crate::delay_item::{jittered, Delayed};
+use crate::diagnostics::{Diagnostics, RecentOperations};
+use crate::listener::{Listener, Outcome};
+use crate::memory::{Eviction, MemoryLimit};
+use crate::quota::{Quota, QuotaExceeded};
+#[cfg(feature = "test-util")]
+use crate::test_util::MockClock;
 
-type MinHeap<T> = BinaryHeap<Reverse<T>>;
+type MinHeap<T> = BinaryHeap<Reverse<Entry<T>>>;
+
+// heap element carrying the queued item along with its bookkeeping
+struct Entry<T> {
+    item: T,
+    // assigned on insertion, orders equal items by insertion
+    seq: u64,
+    listener: Option<Listener>,
+    valid_until: Option<Instant>,
+    // the cancel scope the item was added within
+    scope: Option<u64>,
+    // the jittered release time replacing the item's delay, if the queue has jitter
+    due: Option<Instant>,
+}
+
+impl<T: Ord> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.due
+            .cmp(&other.due)
+            .then_with(|| self.item.cmp(&other.item))
+            .then_with(|| self.seq.cmp(&other.seq))
+    }
+}
+
+impl<T: Ord> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Entry<T> {}
+
+impl<T> Entry<T> {
+    fn new(item: T) -> Self {
+        Entry {
+            item,
+            seq: 0,
+            listener: None,
+            valid_until: None,
+            scope: None,
+            due: None,
+        }
+    }
+
+    fn delay(&self) -> Instant
+    where
+        T: Delayed,
+    {
+        self.due.unwrap_or_else(|| self.item.delay())
+    }
+
+    fn is_stale(&self, now: Instant) -> bool {
+        self.valid_until.is_some_and(|v| v < now)
+    }
+
+    fn notify(self, outcome: Outcome) -> T {
+        if let Some(listener) = self.listener {
+            listener(outcome);
+        }
+        self.item
+    }
+}
 
 /// A blocking queue of [Delayed](delay_item::Delayed) elements in which an element can only be
 /// taken when its delay has expired.
@@ -22,10 +109,240 @@ type MinHeap<T> = BinaryHeap<Reverse<T>>;
 /// let item = bounded_q.take();
 /// println!("{}", item.data);
 /// ```
+///
+/// # Thread safety
+/// The queue is [Send] and [Sync] whenever its elements are [Send], so it can be shared between
+/// threads through an [Arc](std::sync::Arc) without any additional locking.
+/// Callbacks registered on the queue are required to be [Send] + [Sync] and listeners to be [Send],
+/// so they never restrict where the queue can be used.
+/// ```
+/// use std::time::Instant;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// fn assert_send_sync<S: Send + Sync>() {}
+/// assert_send_sync::<BlockingDelayQueue<DelayItem<Vec<u8>>>>();
+/// ```
+/// Elements which aren't [Send] make the queue neither [Send] nor [Sync]:
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// fn assert_send<S: Send>() {}
+/// assert_send::<BlockingDelayQueue<DelayItem<Rc<u8>>>>();
+/// ```
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// fn assert_sync<S: Sync>() {}
+/// assert_sync::<BlockingDelayQueue<DelayItem<Rc<u8>>>>();
+/// ```
 pub struct BlockingDelayQueue<T> {
-    heap: Mutex<MinHeap<T>>,
+    name: Option<String>,
+    state: Mutex<State<T>>,
     condvar: Condvar,
     capacity: usize,
+    pacing: Option<Duration>,
+    tick_driven: bool,
+    on_stale: Option<Box<dyn Fn(T) + Send + Sync>>,
+    on_evict: Option<Box<dyn Fn(T) + Send + Sync>>,
+    soft_limit: Option<(usize, PressureCallback)>,
+    lateness_alert: Option<(Duration, LatenessCallback)>,
+    on_idle: Option<Box<dyn Fn() + Send + Sync>>,
+    // whether the lateness bound is currently exceeded, so the alert fires once per crossing
+    late: AtomicBool,
+    default_offer_timeout: Option<Duration>,
+    default_poll_timeout: Option<Duration>,
+    max_wait_slice: Option<Duration>,
+    early_wake: Option<Duration>,
+    jitter: Option<Duration>,
+    // copies of the state published on every change, read by monitoring calls without locking
+    published: Published,
+    #[cfg(feature = "audit")]
+    audit: Option<Auditor<T>>,
+    #[cfg(feature = "test-util")]
+    clock: Option<MockClock>,
+}
+
+struct Published {
+    len: AtomicUsize,
+    // signed nanos of the head's delay relative to 'created' offset by 'HEAD_BIAS', '0' when there
+    // is no head
+    head: AtomicU64,
+    // set on first publish as queues created in a const context can't read the clock
+    created: OnceLock<Instant>,
+    under_pressure: AtomicBool,
+    // only changed under the lock, so they're consistent with the state when read under it
+    blocked_producers: AtomicUsize,
+    blocked_consumers: AtomicUsize,
+}
+
+const HEAD_BIAS: u64 = 1 << 63;
+
+impl Published {
+    const fn new() -> Self {
+        Published {
+            len: AtomicUsize::new(0),
+            head: AtomicU64::new(0),
+            created: OnceLock::new(),
+            under_pressure: AtomicBool::new(false),
+            blocked_producers: AtomicUsize::new(0),
+            blocked_consumers: AtomicUsize::new(0),
+        }
+    }
+}
+
+enum Released<'a, T> {
+    Item(u64, T),
+    // the guard is handed back along with the time the head can be released
+    Pending(MutexGuard<'a, State<T>>, Option<Instant>),
+}
+
+enum Popped<'a, T> {
+    // the head along with the time it was released at, its delivery isn't recorded yet
+    Entry(MutexGuard<'a, State<T>>, Entry<T>, Instant),
+    Pending(MutexGuard<'a, State<T>>, Option<Instant>),
+}
+
+struct State<T> {
+    heap: MinHeap<T>,
+    // earliest time the next element can be released when delivery is paced
+    next_release: Option<Instant>,
+    // time of the last tick of a tick-driven queue, elements expired by then can be released
+    last_tick: Option<Instant>,
+    next_seq: u64,
+    next_scope: u64,
+    quota: Option<Box<dyn Quota<T> + Send>>,
+    memory: Option<MemoryLimit<T>>,
+    last_adds: RecentOperations,
+    last_takes: RecentOperations,
+    // elements whose delay or ordering panicked when they were added
+    quarantine: Vec<T>,
+}
+
+impl<T: Ord> State<T> {
+    // leaves the entry in the slot until it's accounted for, so a caller catching a panic of
+    // a user implementation can tell whether the entry already made it into the heap
+    fn push(&mut self, slot: &mut Option<Entry<T>>) {
+        let e = slot.as_mut().expect("Entry to push");
+        self.acquire(&e.item);
+        e.seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(Reverse(slot.take().unwrap()));
+    }
+
+    // puts back an entry which was popped, keeping its sequence number
+    fn restore(&mut self, e: Entry<T>) {
+        self.acquire(&e.item);
+        self.heap.push(Reverse(e));
+    }
+
+    fn acquire(&mut self, item: &T) {
+        let bytes = self.memory.as_ref().map(|m| (m.estimate)(item));
+        if let Some(quota) = &mut self.quota {
+            quota.acquire(item);
+        }
+        if let (Some(memory), Some(bytes)) = (&mut self.memory, bytes) {
+            memory.bytes += bytes;
+        }
+    }
+
+    fn remove_seq(&mut self, seq: u64) -> Option<Entry<T>> {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.heap)
+            .into_vec()
+            .into_iter()
+            .partition(|e| e.0.seq == seq);
+        self.heap = BinaryHeap::from(kept);
+        let removed = removed.into_iter().next().map(|e| e.0);
+        if let Some(e) = &removed {
+            self.released(e);
+        }
+        removed
+    }
+
+    fn pop(&mut self) -> Option<Entry<T>> {
+        let e = self.heap.pop()?.0;
+        self.released(&e);
+        Some(e)
+    }
+
+    fn drain(&mut self) -> Vec<Entry<T>> {
+        let entries: Vec<_> = self.heap.drain().map(|e| e.0).collect();
+        for e in &entries {
+            self.released(e);
+        }
+        entries
+    }
+
+    fn remove_scope(&mut self, scope: u64) -> Vec<Entry<T>> {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.heap)
+            .into_vec()
+            .into_iter()
+            .partition(|e| e.0.scope == Some(scope));
+        self.heap = BinaryHeap::from(kept);
+        let removed: Vec<_> = removed.into_iter().map(|e| e.0).collect();
+        for e in &removed {
+            self.released(e);
+        }
+        removed
+    }
+
+    // evicts elements until the estimated memory usage fits into the limit again
+    fn evict(&mut self) -> Vec<Entry<T>> {
+        let mut evicted = Vec::new();
+        let eviction = match self.memory.as_ref().filter(|m| m.is_exceeded()) {
+            Some(memory) => memory.eviction,
+            None => return evicted,
+        };
+        match eviction {
+            Eviction::NearestDeadline => {
+                while self.memory_exceeded() {
+                    match self.heap.pop() {
+                        Some(Reverse(e)) => {
+                            self.released(&e);
+                            evicted.push(e);
+                        }
+                        None => break,
+                    }
+                }
+            }
+            Eviction::FurthestDeadline => {
+                // sorted once per overflow, furthest deadline last
+                let mut entries = std::mem::take(&mut self.heap).into_sorted_vec();
+                entries.reverse();
+                while self.memory_exceeded() {
+                    match entries.pop() {
+                        Some(Reverse(e)) => {
+                            self.released(&e);
+                            evicted.push(e);
+                        }
+                        None => break,
+                    }
+                }
+                self.heap = BinaryHeap::from(entries);
+            }
+        }
+        evicted
+    }
+
+    fn memory_exceeded(&self) -> bool {
+        self.memory.as_ref().is_some_and(|m| m.is_exceeded())
+    }
+
+    fn record_add(&mut self, at: Instant) {
+        self.last_adds.record(at);
+    }
+
+    fn record_take(&mut self, at: Instant) {
+        self.last_takes.record(at);
+    }
+
+    // bookkeeping for every element leaving the heap
+    fn released(&mut self, e: &Entry<T>) {
+        if let Some(quota) = &mut self.quota {
+            quota.release(&e.item);
+        }
+        if let Some(memory) = &mut self.memory {
+            memory.bytes -= (memory.estimate)(&e.item);
+        }
+    }
 }
 
 impl<T> BlockingDelayQueue<T>
@@ -33,6 +350,7 @@ where
     T: Delayed + Ord,
 {
     /// Creates a new unbounded blocking delay queue.
+    /// Being a const fn, it can initialize a `static` queue directly.
     ///
     /// #Examples
     /// Basic usage:
@@ -40,11 +358,50 @@ where
     /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
     /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_unbounded();
     /// ```
-    pub fn new_unbounded() -> Self {
+    ///
+    /// Declaring a global queue:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// static RETRIES: BlockingDelayQueue<DelayItem<u64>> = BlockingDelayQueue::new_unbounded();
+    /// RETRIES.add(DelayItem::new(42, Instant::now()));
+    /// assert_eq!(42, RETRIES.take().data);
+    /// ```
+    pub const fn new_unbounded() -> Self {
         BlockingDelayQueue {
-            heap: Mutex::new(BinaryHeap::new()),
+            name: None,
+            state: Mutex::new(State {
+                heap: BinaryHeap::new(),
+                next_release: None,
+                last_tick: None,
+                next_seq: 0,
+                next_scope: 0,
+                quota: None,
+                memory: None,
+                last_adds: RecentOperations::new(),
+                last_takes: RecentOperations::new(),
+                quarantine: Vec::new(),
+            }),
             condvar: Condvar::new(),
             capacity: 0,
+            pacing: None,
+            tick_driven: false,
+            on_stale: None,
+            on_evict: None,
+            soft_limit: None,
+            lateness_alert: None,
+            on_idle: None,
+            late: AtomicBool::new(false),
+            default_offer_timeout: None,
+            default_poll_timeout: None,
+            max_wait_slice: None,
+            early_wake: None,
+            jitter: None,
+            published: Published::new(),
+            #[cfg(feature = "audit")]
+            audit: None,
+            #[cfg(feature = "test-util")]
+            clock: None,
         }
     }
 
@@ -64,18 +421,83 @@ where
     /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_with_capacity(0);
     /// ```
     pub fn new_with_capacity(capacity: usize) -> Self {
-        if capacity == 0 {
-            Self::new_unbounded()
-        } else {
-            BlockingDelayQueue {
-                heap: Mutex::new(BinaryHeap::with_capacity(capacity)),
-                condvar: Condvar::new(),
-                capacity,
-            }
+        Self::builder().capacity(capacity).build()
+    }
+
+    /// Creates a builder for a blocking delay queue, see [QueueBuilder](crate::QueueBuilder).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::builder().capacity(4).build();
+    /// ```
+    pub fn builder() -> QueueBuilder<T> {
+        QueueBuilder::new()
+    }
+
+    /// Creates a new blocking delay queue as described by the provided configuration.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, QueueConfig};
+    /// let cfg = QueueConfig::new().capacity(4);
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::from_config(&cfg);
+    /// ```
+    pub fn from_config(cfg: &QueueConfig) -> Self {
+        Self::builder().config(cfg).build()
+    }
+
+    pub(crate) fn from_builder(builder: QueueBuilder<T>) -> Self {
+        // spinning for a mock clock would never end
+        #[cfg(feature = "test-util")]
+        let early_wake = builder.early_wake.filter(|_| builder.clock.is_none());
+        #[cfg(not(feature = "test-util"))]
+        let early_wake = builder.early_wake;
+        BlockingDelayQueue {
+            name: builder.name,
+            state: Mutex::new(State {
+                heap: BinaryHeap::with_capacity(builder.capacity),
+                next_release: None,
+                last_tick: None,
+                next_seq: 0,
+                next_scope: 0,
+                quota: builder.quota,
+                memory: builder.memory,
+                last_adds: RecentOperations::new(),
+                last_takes: RecentOperations::new(),
+                quarantine: Vec::new(),
+            }),
+            condvar: Condvar::new(),
+            capacity: builder.capacity,
+            pacing: builder.pacing,
+            tick_driven: builder.tick_driven,
+            on_stale: builder.on_stale,
+            on_evict: builder.on_evict,
+            soft_limit: builder.soft_limit,
+            lateness_alert: builder.lateness_alert,
+            on_idle: builder.on_idle,
+            late: AtomicBool::new(false),
+            default_offer_timeout: builder.default_offer_timeout,
+            default_poll_timeout: builder.default_poll_timeout,
+            max_wait_slice: builder.max_wait_slice,
+            early_wake,
+            jitter: builder.jitter,
+            published: Published::new(),
+            #[cfg(feature = "audit")]
+            audit: builder.audit,
+            #[cfg(feature = "test-util")]
+            clock: builder.clock,
         }
     }
 
     /// Adds an element to this queue waiting if necessary until space becomes available.
+    /// See ['offer_default'] for waiting at most the configured default offer timeout.
+    ///
+    /// # Panics
+    /// With the `debug-checks` feature, panics instead of deadlocking when it would wait for space
+    /// from within one of this queue's callbacks, e.g. a listener run by a consumer.
     ///
     /// #Examples
     /// Basic usage:
@@ -86,23 +508,129 @@ where
     /// queue.add(DelayItem::new(123, Instant::now()));
     /// ```
     pub fn add(&self, e: T) {
-        let mut heap = self.heap_mutex();
-        if self.can_accept_element(&heap) {
-            heap.push(Reverse(e));
-        } else {
-            let cap = heap.capacity();
-            let mut mutex = self
-                .condvar
-                .wait_while(heap, |h| h.len() >= cap)
-                .expect("Queue lock poisoned");
-            mutex.push(Reverse(e));
+        self.add_entry(Entry::new(e))
+    }
+
+    /// Adds an element to this queue like ['add'] unless a pending element matches the provided
+    /// predicate. Checking and adding happen atomically, so concurrent callers can't schedule
+    /// duplicates. While waiting for space, the check is repeated whenever the queue changes.
+    /// Returns 'true' if the element was added, 'false' if a matching element was pending or the
+    /// element was quarantined, see ['drain_quarantined'].
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let at = Instant::now() + Duration::from_secs(60);
+    /// assert!(queue.add_if_absent(|e| e.data == "order-1", DelayItem::new("order-1", at)));
+    /// assert!(!queue.add_if_absent(|e| e.data == "order-1", DelayItem::new("order-1", at)));
+    /// assert_eq!(1, queue.size());
+    /// ```
+    pub fn add_if_absent(&self, pred: impl Fn(&T) -> bool, e: T) -> bool {
+        let mut state = self.state_mutex();
+        loop {
+            if state.heap.iter().any(|pending| pred(&pending.0.item)) {
+                return false;
+            }
+            if self.can_accept_element(&state, &e) {
+                break;
+            }
+            state = self.wait_for_space(state, None, self.now());
         }
+        self.insert(state, Entry::new(e))
+    }
+
+    /// Creates a new cancel scope, which groups the elements added within it through
+    /// ['add_scoped'] so they can all be cancelled at once, see [CancelScope](crate::CancelScope).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_unbounded();
+    /// let connection = queue.cancel_scope();
+    /// ```
+    pub fn cancel_scope(&self) -> CancelScope<'_, T> {
+        let mut state = self.state_mutex();
+        state.next_scope += 1;
+        CancelScope::new(self, state.next_scope)
+    }
+
+    /// Adds an element within the provided cancel scope to this queue, waiting if necessary until
+    /// space becomes available.
+    ///
+    /// # Panics
+    /// Panics if the scope was created by another queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let connection = queue.cancel_scope();
+    /// queue.add_scoped(&connection, DelayItem::new("keep-alive", Instant::now()));
+    /// ```
+    pub fn add_scoped(&self, scope: &CancelScope<'_, T>, e: T) {
+        assert!(
+            std::ptr::eq(self, scope.queue),
+            "Cancel scope belongs to another queue"
+        );
+        let mut entry = Entry::new(e);
+        entry.scope = Some(scope.id);
+        self.add_entry(entry)
+    }
+
+    /// Adds an element to this queue waiting if necessary until space becomes available and
+    /// attaches a one-shot listener to it.
+    /// The listener is invoked with [Delivered](crate::Outcome::Delivered) on the consumer thread
+    /// once the element is taken, or with [Cancelled](crate::Outcome::Cancelled) when the element
+    /// is removed from the queue without being delivered.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, Outcome};
+    /// let  queue = BlockingDelayQueue::new_with_capacity(1);
+    /// queue.add_with_listener(DelayItem::new(123, Instant::now()), |outcome| {
+    ///     assert_eq!(Outcome::Delivered, outcome);
+    /// });
+    /// queue.take();
+    /// ```
+    pub fn add_with_listener(&self, e: T, listener: impl FnOnce(Outcome) + Send + 'static) {
+        self.add_entry(Entry {
+            listener: Some(Box::new(listener)),
+            ..Entry::new(e)
+        })
+    }
 
-        self.condvar.notify_one()
+    /// Adds an element to this queue waiting if necessary until space becomes available, which
+    /// is only delivered if taken no later than the provided time.
+    /// An element that is still in the queue after that time is dropped instead of being delivered,
+    /// or handed to the [on_stale](crate::QueueBuilder::on_stale) callback if one is configured.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let now = Instant::now();
+    /// queue.add_valid_until(DelayItem::new(123, now), now + Duration::from_secs(5));
+    /// ```
+    pub fn add_valid_until(&self, e: T, valid_until: Instant) {
+        self.add_entry(Entry {
+            valid_until: Some(valid_until),
+            ..Entry::new(e)
+        })
     }
 
     /// Adds an element to this queue waiting up to the specified wait time if necessary for space to become available.
-    /// Returns 'true' if insertion was successful within specified wait time 'false' otherwise.
+    /// Returns 'true' if insertion was successful within specified wait time 'false' otherwise,
+    /// including when the element was quarantined, see ['drain_quarantined'].
     ///
     /// #Examples
     /// Basic usage:
@@ -113,28 +641,68 @@ where
     /// queue.offer(DelayItem::new(123, Instant::now()), Duration::from_millis(5));
     /// ```
     pub fn offer(&self, e: T, timeout: Duration) -> bool {
-        let mut heap = self.heap_mutex();
-        if self.can_accept_element(&heap) {
-            heap.push(Reverse(e));
-            self.condvar.notify_one();
-            true
-        } else {
-            let cap = heap.capacity();
-            let mut mutex = self
-                .condvar
-                .wait_timeout_while(heap, timeout, |heap| heap.len() >= cap)
-                .expect("Queue lock poisoned");
-            if mutex.1.timed_out() {
-                false
-            } else {
-                mutex.0.push(Reverse(e));
-                self.condvar.notify_one();
-                true
+        // a timeout too long to represent waits without a deadline
+        let deadline = self.now().checked_add(timeout);
+        let mut state = self.state_mutex();
+        while !self.can_accept_element(&state, &e) {
+            let now = self.now();
+            if deadline.is_some_and(|d| d <= now) {
+                return false;
+            }
+            state = self.wait_for_space(state, deadline, now);
+        }
+        self.insert(state, Entry::new(e))
+    }
+
+    /// Adds an element to this queue like ['offer'] using the queue's
+    /// [default offer timeout](crate::QueueBuilder::default_offer_timeout), or without waiting
+    /// when there is none. ['add'] never applies the default and waits as long as necessary.
+    /// Returns 'true' if insertion was successful within the wait time 'false' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::builder()
+    ///     .capacity(1)
+    ///     .default_offer_timeout(Duration::from_millis(5))
+    ///     .build();
+    /// assert!(queue.offer_default(DelayItem::new(1, Instant::now())));
+    /// assert!(!queue.offer_default(DelayItem::new(2, Instant::now())));
+    /// ```
+    pub fn offer_default(&self, e: T) -> bool {
+        self.offer(e, self.default_offer_timeout.unwrap_or(Duration::ZERO))
+    }
+
+    /// Adds an element to this queue waiting if necessary until space becomes available, unless
+    /// the tenant of the element already reached its [quota](crate::QueueBuilder::quota) of
+    /// pending elements in which case the element is returned immediately as an error.
+    /// Without a configured quota this is equivalent to ['add'].
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::builder().quota(|e: &DelayItem<&str>| e.data, 1).build();
+    /// assert!(queue.try_add(DelayItem::new("tenant", Instant::now())).is_ok());
+    /// assert!(queue.try_add(DelayItem::new("tenant", Instant::now())).is_err());
+    /// ```
+    pub fn try_add(&self, e: T) -> Result<(), QuotaExceeded<T>> {
+        let mut state = self.state_mutex();
+        while !self.can_accept_element(&state, &e) {
+            if !Self::within_quota(&state, &e) {
+                return Err(QuotaExceeded(e));
             }
+            state = self.wait_for_space(state, None, self.now());
         }
+        self.insert(state, Entry::new(e));
+        Ok(())
     }
 
     /// Retrieves and removes the head of this queue, waiting if necessary until an element with an expired delay is available on this queue.
+    /// See ['poll_default'] for waiting at most the configured default poll timeout.
     ///
     /// #Examples
     /// Basic usage:
@@ -147,10 +715,23 @@ where
     /// println!("{}", item.data);
     /// ```
     pub fn take(&self) -> T {
-        match self.wait_for_element(Duration::ZERO) {
-            Some(e) => e,
-            _ => unreachable!(),
-        }
+        self.take_with_seq().1
+    }
+
+    /// Retrieves and removes the head of this queue like ['take'] and returns it transformed by
+    /// the provided function, which runs after the element left the queue without holding its lock.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(vec![0u8; 1024], Instant::now()));
+    /// assert_eq!(1024, queue.take_map(|e| e.data.len()));
+    /// ```
+    pub fn take_map<R>(&self, f: impl FnOnce(T) -> R) -> R {
+        f(self.take())
     }
 
     /// Retrieves and removes the head of this queue, waiting if necessary until an element with an expired delay is available on this queue, or the specified wait time expires.
@@ -168,229 +749,2276 @@ where
     /// println!("{}", polled.unwrap().data);
     /// ```
     pub fn poll(&self, timeout: Duration) -> Option<T> {
-        self.wait_for_element(timeout)
+        self.poll_with_seq(timeout).map(|e| e.1)
     }
 
-    /// Returns the number of elements in this queue.
+    /// Retrieves and removes the head of this queue like ['poll'] using the queue's
+    /// [default poll timeout](crate::QueueBuilder::default_poll_timeout), or without waiting
+    /// when there is none. ['take'] never applies the default and waits as long as necessary.
     ///
     /// #Examples
     /// Basic usage:
     /// ```
-    /// use std::time::{Duration, Instant};
+    /// use std::time::Duration;
     /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
-    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_with_capacity(1);
-    /// println!("{}", queue.size());
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .default_poll_timeout(Duration::from_millis(5))
+    ///     .build();
+    /// assert!(queue.poll_default().is_none());
     /// ```
-    pub fn size(&self) -> usize {
-        self.heap_mutex().len()
+    pub fn poll_default(&self) -> Option<T> {
+        self.poll(self.default_poll_timeout.unwrap_or(Duration::ZERO))
     }
 
-    /// Removes all of the elements from this queue.
+    /// Retrieves and removes the head of this queue like ['take'], along with the sequence number
+    /// stamped on it when it was accepted.
+    /// Sequence numbers increase monotonically with every accepted element, so consumers can
+    /// reconstruct enqueue order or detect elements which were never delivered.
     ///
     /// #Examples
     /// Basic usage:
     /// ```
-    /// use std::time::{Duration, Instant};
+    /// use std::time::Instant;
     /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
-    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_with_capacity(1);
-    /// queue.clear();
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(123, Instant::now()));
+    /// let (seq, item) = queue.take_with_seq();
+    /// assert_eq!(0, seq);
     /// ```
-    pub fn clear(&self) {
-        self.heap_mutex().clear();
-        self.condvar.notify_all();
+    pub fn take_with_seq(&self) -> (u64, T) {
+        match self.wait_for_element(None) {
+            Some(e) => e,
+            _ => unreachable!(),
+        }
     }
 
-    fn heap_mutex(&self) -> MutexGuard<'_, MinHeap<T>> {
-        self.heap.lock().expect("Queue lock poisoned")
+    /// Retrieves and removes the head of this queue like ['poll'], along with the sequence number
+    /// stamped on it when it was accepted, see ['take_with_seq'].
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(123, Instant::now()));
+    /// let polled = queue.poll_with_seq(Duration::from_secs(1));
+    /// assert_eq!(Some(0), polled.map(|e| e.0));
+    /// ```
+    pub fn poll_with_seq(&self, timeout: Duration) -> Option<(u64, T)> {
+        self.wait_for_element(self.now().checked_add(timeout))
     }
 
-    fn wait_for_element(&self, timeout: Duration) -> Option<T> {
-        let heap = self.heap_mutex();
-        if let Some(e) = heap.peek() {
-            let current_time = Instant::now();
-            if Self::is_expired(&e.0) {
-                // remove head
-                Some(self.pop_and_notify(heap))
-            } else {
-                let delay = match timeout {
-                    // delay until head expiration
-                    Duration::ZERO => e.0.delay() - current_time,
-                    // delay until timeout
-                    _ => timeout,
-                };
-
-                // wait until condition is satisfied respecting timeout (delay)
-                match self.wait_for_element_with_timeout(heap, delay) {
-                    // available element within timeout
-                    Some(e) => Some(e),
-                    _ => {
-                        match timeout {
-                            // unreachable code but let's keep it in case Q.remove(e) is added
-                            Duration::ZERO => self.wait_for_element(timeout),
-                            // when within timeout there is no available element
-                            _ => None,
-                        }
-                    }
+    /// Retrieves and removes up to 'max' expired elements, waiting until at least 'min' elements
+    /// are expired or the specified maximum wait time elapses, whichever comes first.
+    /// Elements count once they can be taken, i.e. respecting ticks, pacing and staleness, and
+    /// are taken into the batch right away, so they can't be lost to other consumers meanwhile.
+    /// The returned batch can contain fewer than 'min' elements, or none at all, when the wait time elapsed.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// for i in 0..3 {
+    ///     queue.add(DelayItem::new(i, Instant::now()));
+    /// }
+    /// let batch = queue.take_at_least(2, 10, Duration::from_secs(1));
+    /// assert_eq!(3, batch.len());
+    /// ```
+    pub fn take_at_least(&self, min: usize, max: usize, max_wait: Duration) -> Vec<T> {
+        let deadline = self.now().checked_add(max_wait);
+        let mut batch = Vec::new();
+        let mut state = self.state_mutex();
+        // the batch itself is the running count, only the head is checked on every wakeup
+        while batch.len() < max {
+            let release_at = match self.try_release(state, None) {
+                Released::Item(_, e) => {
+                    batch.push(e);
+                    state = self.state_mutex();
+                    continue;
                 }
-            }
-        } else {
-            match timeout {
-                Duration::ZERO => {
-                    let guard = self
-                        .condvar
-                        .wait_while(heap, Self::wait_condition())
-                        .expect("Condvar lock poisoned");
-
-                    Some(self.pop_and_notify(guard))
+                Released::Pending(s, at) => {
+                    state = s;
+                    at
                 }
-                _ => self.wait_for_element_with_timeout(heap, timeout),
+            };
+            let now = self.now();
+            if batch.len() >= min || deadline.is_some_and(|d| d <= now) {
+                break;
             }
+            state = self.wait_for_release(state, release_at, deadline, now);
         }
+        batch
     }
 
-    fn wait_for_element_with_timeout(
-        &self,
-        heap: MutexGuard<MinHeap<T>>,
-        timeout: Duration,
-    ) -> Option<T> {
-        let (heap, res) = self
-            .condvar
-            .wait_timeout_while(heap, timeout, Self::wait_condition())
-            .expect("Condvar lock poisoned");
-
-        match res.timed_out() {
-            true => None,
-            _ => Some(self.pop_and_notify(heap)),
+    /// Appends up to 'max' expired elements to the provided buffer, waiting if necessary until
+    /// at least one element is expired or the specified wait time expires.
+    /// Returns the number of appended elements, which is '0' if no element expired within the
+    /// wait time. Reusing the buffer avoids allocating a new batch on every call.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let mut buf = Vec::with_capacity(16);
+    /// for i in 0..3 {
+    ///     queue.add(DelayItem::new(i, Instant::now()));
+    /// }
+    /// assert_eq!(3, queue.take_into(&mut buf, 16, Duration::from_secs(1)));
+    /// buf.clear();
+    /// assert_eq!(0, queue.take_into(&mut buf, 16, Duration::from_millis(1)));
+    /// ```
+    pub fn take_into(&self, buf: &mut Vec<T>, max: usize, timeout: Duration) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        match self.wait_for_element(self.now().checked_add(timeout)) {
+            Some((_, e)) => {
+                buf.push(e);
+                1 + self.release_into(self.state_mutex(), max - 1, |e| buf.push(e))
+            }
+            _ => 0,
         }
     }
 
-    fn pop_and_notify(&self, mut mutex: MutexGuard<MinHeap<T>>) -> T {
-        let e = mutex.pop().unwrap().0;
-        self.condvar.notify_one();
-        e
-    }
-
-    fn can_accept_element(&self, m: &MutexGuard<MinHeap<T>>) -> bool {
-        if self.capacity == 0 {
-            true
-        } else {
-            m.len() < m.capacity()
+    /// Writes up to 'buf.len()' expired elements into the provided slots, waiting if necessary
+    /// until at least one element is expired or the specified wait time expires.
+    /// Returns the number 'n' of written elements, after which the first 'n' slots are initialized
+    /// and owned by the caller. The queue itself doesn't allocate while taking, so this can be used
+    /// on consumer threads which must not allocate, as long as the taken elements carry no
+    /// listener and no audit sink or callback is configured, as those run on the consumer thread.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::mem::MaybeUninit;
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(1, Instant::now()));
+    /// queue.add(DelayItem::new(2, Instant::now()));
+    /// let mut slots = [MaybeUninit::uninit(), MaybeUninit::uninit(), MaybeUninit::uninit()];
+    /// let n = queue.take_into_slice(&mut slots, Duration::from_secs(1));
+    /// assert_eq!(2, n);
+    /// // safety: the first 'n' slots were initialized by the queue
+    /// let first = unsafe { slots[0].assume_init_read() };
+    /// let second = unsafe { slots[1].assume_init_read() };
+    /// assert_eq!((1, 2), (first.data, second.data));
+    /// ```
+    pub fn take_into_slice(&self, buf: &mut [MaybeUninit<T>], timeout: Duration) -> usize {
+        let mut slots = buf.iter_mut();
+        let first = match slots.next() {
+            Some(slot) => slot,
+            _ => return 0,
+        };
+        match self.wait_for_element(self.now().checked_add(timeout)) {
+            Some((_, e)) => {
+                first.write(e);
+                let max = slots.len();
+                1 + self.release_into(self.state_mutex(), max, |e| {
+                    slots.next().unwrap().write(e);
+                })
+            }
+            _ => 0,
         }
     }
 
-    fn wait_condition() -> impl Fn(&mut MinHeap<T>) -> bool {
-        move |heap: &mut MinHeap<T>| {
-            heap.peek()
-                .map_or(true, |item| item.0.delay() > Instant::now())
+    /// Retrieves and removes the head of this queue, waiting at most the provided time slice until
+    /// an element with an expired delay is available.
+    /// Instead of an empty result, a consumer whose slice ran out learns when the head of the
+    /// queue can be released, so cooperative schedulers can yield and come back at that time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, Budgeted, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let deadline = Instant::now() + Duration::from_secs(60);
+    /// queue.add(DelayItem::new(123, deadline));
+    /// match queue.take_budgeted(Duration::from_millis(1)) {
+    ///     Budgeted::Item(e) => println!("{}", e.data),
+    ///     Budgeted::WouldBlockUntil(at) => assert_eq!(deadline, at),
+    ///     Budgeted::Empty => println!("nothing scheduled"),
+    /// }
+    /// ```
+    pub fn take_budgeted(&self, max_block: Duration) -> Budgeted<T> {
+        let deadline = self.now().checked_add(max_block);
+        let mut state = self.state_mutex();
+        loop {
+            let release_at = match self.try_release(state, None) {
+                Released::Item(_, e) => return Budgeted::Item(e),
+                Released::Pending(s, at) => {
+                    state = s;
+                    at
+                }
+            };
+            let now = self.now();
+            if deadline.is_some_and(|d| d <= now) {
+                return match release_at {
+                    Some(at) => Budgeted::WouldBlockUntil(at),
+                    _ => Budgeted::Empty,
+                };
+            }
+            state = self.wait_for_release(state, release_at, deadline, now);
+        }
+    }
+
+    /// Retrieves and removes the head of this queue only if its delay has already expired,
+    /// without ever waiting.
+    /// Returns [None](core::option::Option::None) if there is no expired element.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(123, Instant::now() + Duration::from_secs(60)));
+    /// assert!(queue.expired().is_none());
+    /// ```
+    pub fn expired(&self) -> Option<T> {
+        match self.try_release(self.state_mutex(), None) {
+            Released::Item(_, e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of elements in this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_with_capacity(1);
+    /// println!("{}", queue.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.published.len.load(AtomicOrdering::Acquire)
+    }
+
+    /// Returns 'true' if this queue contains no elements.
+    /// Like ['size'] and ['next_expiration'] it never waits for the queue lock, so monitoring
+    /// doesn't slow down producers and consumers.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_unbounded();
+    /// assert!(queue.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    /// Returns the delay of the head of this queue, i.e. when the next element can be taken,
+    /// or [None](core::option::Option::None) if this queue is empty.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let at = Instant::now() + Duration::from_secs(60);
+    /// queue.add(DelayItem::new(1, at));
+    /// assert_eq!(Some(at), queue.next_expiration());
+    /// ```
+    pub fn next_expiration(&self) -> Option<Instant> {
+        match self.published.head.load(AtomicOrdering::Acquire) {
+            0 => None,
+            head => {
+                let created = *self
+                    .published
+                    .created
+                    .get()
+                    .expect("head published before creation");
+                match head.wrapping_sub(HEAD_BIAS) as i64 {
+                    nanos if nanos >= 0 => Some(created + Duration::from_nanos(nanos as u64)),
+                    nanos => Some(created - Duration::from_nanos(nanos.unsigned_abs())),
+                }
+            }
+        }
+    }
+
+    /// Returns the number of elements in this queue whose delay expires within the provided
+    /// duration from now, including already expired elements.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(30)));
+    /// queue.add(DelayItem::new(2, Instant::now() + Duration::from_secs(3600)));
+    /// assert_eq!(1, queue.count_due_within(Duration::from_secs(60)));
+    /// ```
+    pub fn count_due_within(&self, within: Duration) -> usize {
+        let horizon = self.now().checked_add(within);
+        self.state_mutex()
+            .heap
+            .iter()
+            .filter(|e| horizon.is_none_or(|h| e.0.delay() <= h))
+            .count()
+    }
+
+    /// Returns a histogram of element delays relative to now.
+    /// Buckets are ascending upper bounds where bucket 'i' counts elements due after bound 'i - 1'
+    /// and within bound 'i'; already expired elements fall into the first bucket.
+    /// The returned histogram has an additional last bucket counting elements due after the last bound.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(30)));
+    /// queue.add(DelayItem::new(2, Instant::now() + Duration::from_secs(600)));
+    /// let minute = Duration::from_secs(60);
+    /// let hour = Duration::from_secs(3600);
+    /// assert_eq!(vec![1, 1, 0], queue.deadline_histogram(&[minute, hour]));
+    /// ```
+    pub fn deadline_histogram(&self, buckets: &[Duration]) -> Vec<usize> {
+        let now = self.now();
+        let mut histogram = vec![0; buckets.len() + 1];
+        for e in self.state_mutex().heap.iter() {
+            let due_in = e.0.delay().saturating_duration_since(now);
+            let bucket = buckets.partition_point(|bound| *bound < due_in);
+            histogram[bucket] += 1;
+        }
+        histogram
+    }
+
+    /// Calls the provided function with a read-only view of all elements in this queue, ordered
+    /// the way they will be delivered, and returns its result.
+    /// The queue stays locked while the function runs, so it should be quick and must not
+    /// call back into this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(2, Instant::now() + Duration::from_secs(60)));
+    /// queue.add(DelayItem::new(1, Instant::now()));
+    /// let next: Vec<_> = queue.with_items(|items| items.iter().map(|e| e.data).collect());
+    /// assert_eq!(vec![1, 2], next);
+    /// ```
+    pub fn with_items<R>(&self, f: impl FnOnce(&[&T]) -> R) -> R {
+        let state = self.state_mutex();
+        let mut entries: Vec<&Entry<T>> = state.heap.iter().map(|e| &e.0).collect();
+        entries.sort_unstable();
+        let items: Vec<&T> = entries.into_iter().map(|e| &e.item).collect();
+        f(&items)
+    }
+
+    /// Finds the element matching the provided predicate which will be delivered first, without
+    /// removing it, and returns the result of the provided function applied to it.
+    /// Returns [None](core::option::Option::None) if no element matches.
+    /// The queue stays locked while the functions run, so they should be quick and must not
+    /// call back into this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// let at = Instant::now() + Duration::from_secs(60);
+    /// queue.add(DelayItem::new("order-1", at));
+    /// assert_eq!(Some(at), queue.find_map_where(|e| e.data == "order-1", |e| e.delay));
+    /// assert_eq!(None, queue.find_map_where(|e| e.data == "order-2", |e| e.delay));
+    /// ```
+    pub fn find_map_where<R>(
+        &self,
+        pred: impl Fn(&T) -> bool,
+        f: impl FnOnce(&T) -> R,
+    ) -> Option<R> {
+        let state = self.state_mutex();
+        let first = state
+            .heap
+            .iter()
+            .map(|e| &e.0)
+            .filter(|e| pred(&e.item))
+            .min()?;
+        Some(f(&first.item))
+    }
+
+    /// Returns the number of elements in this queue matching the provided predicate.
+    /// The queue stays locked while the predicate runs, so it should be quick and must not
+    /// call back into this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(("order-1", "reminder"), Instant::now()));
+    /// queue.add(DelayItem::new(("order-1", "expiry"), Instant::now()));
+    /// queue.add(DelayItem::new(("order-2", "reminder"), Instant::now()));
+    /// assert_eq!(2, queue.count_where(|e| e.data.0 == "order-1"));
+    /// ```
+    pub fn count_where(&self, pred: impl Fn(&T) -> bool) -> usize {
+        self.state_mutex()
+            .heap
+            .iter()
+            .filter(|e| pred(&e.0.item))
+            .count()
+    }
+
+    /// Returns the number of elements in this queue whose delay has already expired, i.e. which
+    /// are waiting for a consumer.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(1, Instant::now()));
+    /// queue.add(DelayItem::new(2, Instant::now() + Duration::from_secs(60)));
+    /// assert_eq!(1, queue.expired_count());
+    /// ```
+    pub fn expired_count(&self) -> usize {
+        self.count_due_within(Duration::ZERO)
+    }
+
+    /// Returns how long ago the delay of the longest waiting expired element expired, or
+    /// [None](core::option::Option::None) if no element is expired.
+    /// A growing age means expired elements pile up because consumers don't keep up.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// assert!(queue.oldest_expired_age().is_none());
+    /// queue.add(DelayItem::new(1, Instant::now() - Duration::from_secs(5)));
+    /// assert!(queue.oldest_expired_age().unwrap() >= Duration::from_secs(5));
+    /// ```
+    pub fn oldest_expired_age(&self) -> Option<Duration> {
+        let now = self.now();
+        self.state_mutex()
+            .heap
+            .iter()
+            .map(|e| e.0.delay())
+            .filter(|delay| *delay <= now)
+            .min()
+            .map(|delay| now - delay)
+    }
+
+    /// Returns the number of producers currently blocked in ['add'] / ['offer'] until space
+    /// becomes available. Producers blocking for long indicate a full queue and slow consumers.
+    /// Like ['size'], it's read without taking the queue lock.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_with_capacity(1);
+    /// assert_eq!(0, queue.blocked_producers());
+    /// ```
+    pub fn blocked_producers(&self) -> usize {
+        self.published
+            .blocked_producers
+            .load(AtomicOrdering::Acquire)
+    }
+
+    /// Returns the number of consumers currently blocked in ['take'] / ['poll'] until an element
+    /// can be taken. Consumers blocking while producers don't indicate the queue is starved.
+    /// Like ['size'], it's read without taking the queue lock.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_unbounded();
+    /// assert_eq!(0, queue.blocked_consumers());
+    /// ```
+    pub fn blocked_consumers(&self) -> usize {
+        self.published
+            .blocked_consumers
+            .load(AtomicOrdering::Acquire)
+    }
+
+    /// Releases the elements of a [tick-driven](crate::QueueBuilder::tick_driven) queue whose
+    /// delay expired by now, waking consumers blocked in ['take'] / ['poll'] to take them.
+    /// Elements expiring later wait for a following tick. Has no effect on other queues.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::builder().tick_driven().build();
+    /// queue.add(DelayItem::new("frame", Instant::now()));
+    /// assert!(queue.expired().is_none());
+    /// queue.tick();
+    /// assert_eq!("frame", queue.expired().unwrap().data);
+    /// ```
+    pub fn tick(&self) {
+        let mut state = self.state_mutex();
+        state.last_tick = Some(self.now());
+        self.condvar.notify_all();
+    }
+
+    /// Waits until this queue is empty and no producer is blocked, e.g. for finishing a batch
+    /// job once its workers took all pending elements.
+    /// Returns immediately if the queue is idle already. See
+    /// [on_idle](crate::QueueBuilder::on_idle) for a callback instead.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::thread;
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// queue.add(DelayItem::new("job", Instant::now()));
+    /// let worker = queue.clone();
+    /// thread::spawn(move || worker.take());
+    /// queue.wait_idle();
+    /// assert!(queue.is_empty());
+    /// ```
+    pub fn wait_idle(&self) {
+        let mut state = self.state_mutex();
+        while !self.is_idle(&state) {
+            state = self.wait_until(state, None, self.now());
+        }
+    }
+
+    /// Captures a point-in-time view of this queue for bug reports and health endpoints, e.g. to
+    /// tell whether a stall is caused by producers blocked on a full queue or by slow consumers.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new(1, Instant::now() - Duration::from_secs(5)));
+    /// queue.add(DelayItem::new(2, Instant::now() + Duration::from_secs(60)));
+    /// let diagnostics = queue.diagnostics();
+    /// assert_eq!((2, 1), (diagnostics.pending, diagnostics.expired));
+    /// assert!(diagnostics.oldest_expired_age.unwrap() >= Duration::from_secs(5));
+    /// assert_eq!(2, diagnostics.last_adds.len());
+    /// println!("{:?}", diagnostics);
+    /// ```
+    pub fn diagnostics(&self) -> Diagnostics {
+        let state = self.state_mutex();
+        let now = self.now();
+        let expired: Vec<_> = state
+            .heap
+            .iter()
+            .map(|e| e.0.delay())
+            .filter(|delay| *delay <= now)
+            .collect();
+        Diagnostics {
+            pending: state.heap.len(),
+            expired: expired.len(),
+            oldest_expired_age: expired.iter().min().map(|delay| now - *delay),
+            blocked_producers: self.blocked_producers(),
+            blocked_consumers: self.blocked_consumers(),
+            last_adds: state.last_adds.iter().collect(),
+            last_takes: state.last_takes.iter().collect(),
+        }
+    }
+
+    /// Returns the name of this queue set by [name](crate::QueueBuilder::name), or
+    /// [None](core::option::Option::None) if it's unnamed.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::builder().name("orders").build();
+    /// assert_eq!(Some("orders"), queue.name());
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns 'true' while the queue holds at least as many elements as its
+    /// [soft limit](crate::QueueBuilder::soft_limit), 'false' otherwise or if there is none.
+    /// Producers can check it to shed load before a bounded queue starts blocking them.
+    /// Like ['size'], it's read without taking the queue lock.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::builder()
+    ///     .soft_limit(1, |size| println!("{} elements pending", size))
+    ///     .build();
+    /// assert!(!queue.is_under_pressure());
+    /// queue.add(DelayItem::new(1, Instant::now()));
+    /// assert!(queue.is_under_pressure());
+    /// ```
+    pub fn is_under_pressure(&self) -> bool {
+        self.published.under_pressure.load(AtomicOrdering::Acquire)
+    }
+
+    /// Removes and returns the elements which were quarantined instead of being added because
+    /// their [delay](crate::Delayed::delay) or [Ord] implementation panicked, so they can be
+    /// inspected. Quarantined elements are reported as cancelled to their listeners, and adding
+    /// methods returning whether the element was added report them as not added.
+    /// An element is checked while it's added, by computing its delay and ordering it within the
+    /// queue; a panic in a later comparison, while the element is already queued, still poisons
+    /// the queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_unbounded();
+    /// assert!(queue.drain_quarantined().is_empty());
+    /// ```
+    pub fn drain_quarantined(&self) -> Vec<T> {
+        std::mem::take(&mut self.state_mutex().quarantine)
+    }
+
+    /// Removes all of the elements from this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let  queue = BlockingDelayQueue::<DelayItem<&str>>::new_with_capacity(1);
+    /// queue.clear();
+    /// ```
+    pub fn clear(&self) {
+        let (entries, idle) = {
+            let mut state = self.state_mutex();
+            let entries = state.drain();
+            self.publish(&state);
+            let idle = !entries.is_empty() && self.is_idle(&state);
+            (entries, idle)
+        };
+        self.condvar.notify_all();
+        for e in entries {
+            self.finish(e, Outcome::Cancelled);
+        }
+        if idle {
+            self.notify_idle();
+        }
+    }
+
+    // the time of the mock clock driving this queue, the system time otherwise
+    fn now(&self) -> Instant {
+        #[cfg(feature = "test-util")]
+        if let Some(clock) = &self.clock {
+            return clock.now();
+        }
+        Instant::now()
+    }
+
+    fn state_mutex(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|_| self.poisoned())
+    }
+
+    fn poisoned(&self) -> ! {
+        match &self.name {
+            Some(name) => panic!("Queue '{}' lock poisoned", name),
+            _ => panic!("Queue lock poisoned"),
+        }
+    }
+
+    pub(crate) fn cancel_scoped(&self, scope: u64) -> usize {
+        let (entries, idle) = {
+            let mut state = self.state_mutex();
+            let entries = state.remove_scope(scope);
+            self.publish(&state);
+            let idle = !entries.is_empty() && self.is_idle(&state);
+            (entries, idle)
+        };
+        self.condvar.notify_all();
+        let cancelled = entries.len();
+        for e in entries {
+            self.finish(e, Outcome::Cancelled);
+        }
+        if idle {
+            self.notify_idle();
+        }
+        cancelled
+    }
+
+    // waits for an expired element like 'take' and forwards it without releasing the lock, so an
+    // element the target refuses is restored at the head with its sequence number before anyone
+    // notices. Its delivery is only recorded, and listeners notified, once the target accepted it.
+    // Gives up returning None once the flag is cleared and consumers are woken up by
+    // 'wake_consumers'.
+    pub(crate) fn forward_while(
+        &self,
+        running: &AtomicBool,
+        forward: impl FnOnce(T) -> Result<(), TrySendError<T>>,
+    ) -> Option<Result<(), TrySendError<()>>> {
+        let mut state = self.state_mutex();
+        let (mut state, e, now) = loop {
+            if !running.load(AtomicOrdering::Acquire) {
+                return None;
+            }
+            match self.pop_releasable(state, None) {
+                Popped::Entry(state, e, now) => break (state, e, now),
+                Popped::Pending(s, at) => {
+                    state = self.wait_for_release(s, at, None, self.now());
+                }
+            }
+        };
+        let delay = e.delay();
+        let Entry {
+            item,
+            seq,
+            listener,
+            valid_until,
+            scope,
+            due,
+        } = e;
+        #[cfg(feature = "audit")]
+        let taken = self
+            .audit
+            .as_ref()
+            .map(|a| a.prepare(self.name(), AuditOperation::Take, &item));
+        let (item, refused) = match forward(item) {
+            Ok(()) => {
+                self.record_delivery(state, delay, now);
+                if let Some(listener) = listener {
+                    self.dispatch(|| listener(Outcome::Delivered));
+                }
+                #[cfg(feature = "audit")]
+                if let (Some(audit), Some(taken)) = (&self.audit, taken) {
+                    audit.record(taken);
+                }
+                return Some(Ok(()));
+            }
+            Err(TrySendError::Full(item)) => (item, TrySendError::Full(())),
+            Err(TrySendError::Disconnected(item)) => (item, TrySendError::Disconnected(())),
+        };
+        state.restore(Entry {
+            item,
+            seq,
+            listener,
+            valid_until,
+            scope,
+            due,
+        });
+        self.publish(&state);
+        Some(Err(refused))
+    }
+
+    pub(crate) fn wake_consumers(&self) {
+        // taking the lock guarantees that waiting consumers either observe the change or are notified
+        let _state = self.state_mutex();
+        self.condvar.notify_all();
+    }
+
+    // maps every element under the lock, in heap order
+    #[cfg(feature = "serde")]
+    pub(crate) fn map_elements<R>(&self, f: impl FnMut(&T) -> R) -> Vec<R> {
+        self.state_mutex()
+            .heap
+            .iter()
+            .map(|e| &e.0.item)
+            .map(f)
+            .collect()
+    }
+
+    fn add_entry(&self, e: Entry<T>) {
+        let mut state = self.state_mutex();
+        while !self.can_accept_element(&state, &e.item) {
+            state = self.wait_for_space(state, None, self.now());
+        }
+        self.insert(state, e);
+    }
+
+    // returns 'false' if the element was quarantined instead of being added
+    fn insert(&self, mut state: MutexGuard<State<T>>, e: Entry<T>) -> bool {
+        #[cfg(feature = "audit")]
+        let added = self
+            .audit
+            .as_ref()
+            .map(|a| a.prepare(self.name(), AuditOperation::Add, &e.item));
+        let mut slot = Some(e);
+        // a panic caught before unwinding through the guard doesn't poison the lock
+        let pushed = panic::catch_unwind(AssertUnwindSafe(|| {
+            if let Some(e) = slot.as_mut() {
+                let delay = e.item.delay();
+                e.due = self.jitter.map(|jitter| jittered(delay, jitter));
+            }
+            state.push(&mut slot);
+        }));
+        if pushed.is_err() {
+            // an entry which made it into the heap panicked while being ordered
+            let e = match slot {
+                Some(e) => e,
+                _ => {
+                    let seq = state.next_seq - 1;
+                    state.remove_seq(seq).expect("Pushed entry")
+                }
+            };
+            self.publish(&state);
+            drop(state);
+            // never added, so it's not audited as cancelled
+            let item = self.dispatch(|| e.notify(Outcome::Cancelled));
+            self.state_mutex().quarantine.push(item);
+            return false;
+        }
+        state.record_add(self.now());
+        let evicted = state.evict();
+        let was_under_pressure = self.is_under_pressure();
+        self.publish(&state);
+        let crossed = !was_under_pressure && self.is_under_pressure();
+        let late = self.lateness_crossed(&state, self.now(), Duration::ZERO);
+        // the added element itself may have been evicted
+        let idle = !evicted.is_empty() && self.is_idle(&state);
+        self.condvar.notify_all();
+        // release the lock before reporting evictions
+        drop(state);
+        if let (true, Some((_, on_pressure))) = (crossed, &self.soft_limit) {
+            self.dispatch(|| on_pressure(self.size()));
+        }
+        self.alert_lateness(late);
+        #[cfg(feature = "audit")]
+        if let (Some(audit), Some(added)) = (&self.audit, added) {
+            audit.record(added);
+        }
+        for e in evicted {
+            let item = self.finish(e, Outcome::Cancelled);
+            if let Some(on_evict) = &self.on_evict {
+                self.dispatch(|| on_evict(item));
+            }
+        }
+        if idle {
+            self.notify_idle();
+        }
+        true
+    }
+
+    fn wait_for_element(&self, deadline: Option<Instant>) -> Option<(u64, T)> {
+        let mut state = self.state_mutex();
+        loop {
+            let release_at = match self.try_release(state, None) {
+                Released::Item(seq, e) => return Some((seq, e)),
+                Released::Pending(s, at) => {
+                    state = s;
+                    at
+                }
+            };
+            let now = self.now();
+            if deadline.is_some_and(|d| d <= now) {
+                return None;
+            }
+
+            // wait until the head can be released, the deadline expires or the queue changes
+            state = self.wait_for_release(state, release_at, deadline, now);
+        }
+    }
+
+    // waits like 'wait_until' while counted as a producer blocked until space becomes available
+    fn wait_for_space<'a>(
+        &'a self,
+        state: MutexGuard<'a, State<T>>,
+        until: Option<Instant>,
+        now: Instant,
+    ) -> MutexGuard<'a, State<T>> {
+        #[cfg(feature = "debug-checks")]
+        if until.is_none() && debug_checks::is_dispatching(self as *const Self as usize) {
+            drop(state);
+            panic!(
+                "Blocking add to full queue '{}' from one of its own callbacks would deadlock, use offer or try_add instead",
+                self.name().unwrap_or("unnamed")
+            );
+        }
+        let blocked = &self.published.blocked_producers;
+        blocked.fetch_add(1, AtomicOrdering::AcqRel);
+        let state = self.wait_until(state, until, now);
+        blocked.fetch_sub(1, AtomicOrdering::AcqRel);
+        state
+    }
+
+    // waits until the head can be released at 'release_at' or the deadline expires, while counted
+    // as a blocked consumer; only this wait wakes early and spins for the head's release time
+    fn wait_for_release<'a>(
+        &'a self,
+        state: MutexGuard<'a, State<T>>,
+        release_at: Option<Instant>,
+        deadline: Option<Instant>,
+        now: Instant,
+    ) -> MutexGuard<'a, State<T>> {
+        let blocked = &self.published.blocked_consumers;
+        blocked.fetch_add(1, AtomicOrdering::AcqRel);
+        let state = match (release_at, self.early_wake) {
+            (Some(at), Some(early)) if deadline.is_none_or(|d| at <= d) => {
+                if at.saturating_duration_since(now) <= early {
+                    self.spin_until(state, at, now)
+                } else {
+                    self.wait_until(state, Some(at - early), now)
+                }
+            }
+            _ => self.wait_until(state, Self::earliest(release_at, deadline), now),
+        };
+        blocked.fetch_sub(1, AtomicOrdering::AcqRel);
+        state
+    }
+
+    // busy-waits for the head's release time without holding the lock, so producers and other
+    // consumers can proceed, but stops early once the head changes or the wait slice is over
+    fn spin_until<'a>(
+        &'a self,
+        state: MutexGuard<'a, State<T>>,
+        at: Instant,
+        now: Instant,
+    ) -> MutexGuard<'a, State<T>> {
+        let until = match self.max_wait_slice {
+            Some(slice) => now.checked_add(slice).map_or(at, |end| at.min(end)),
+            _ => at,
+        };
+        drop(state);
+        while self.now() < until {
+            match self.state.try_lock() {
+                Ok(state) => {
+                    let head = state.heap.peek().map(|e| self.release_time(&state, &e.0));
+                    if head != Some(at) {
+                        break;
+                    }
+                }
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Poisoned(_)) => break,
+            }
+            hint::spin_loop();
+        }
+        self.state_mutex()
+    }
+
+    // waits for a notification or until the provided time, but never longer than one wait slice
+    fn wait_until<'a>(
+        &'a self,
+        state: MutexGuard<'a, State<T>>,
+        until: Option<Instant>,
+        now: Instant,
+    ) -> MutexGuard<'a, State<T>> {
+        deadline_wait::wait_until(&self.condvar, state, until, now, self.max_wait_slice)
+            .unwrap_or_else(|_| self.poisoned())
+    }
+
+    fn release_time(&self, state: &State<T>, e: &Entry<T>) -> Instant {
+        let delay = e.delay();
+        match state.next_release {
+            Some(next) if next > delay => next,
+            _ => delay,
+        }
+    }
+
+    fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn publish(&self, state: &State<T>) {
+        let created = *self.published.created.get_or_init(Instant::now);
+        let head = state.heap.peek().map_or(0, |e| {
+            let delay = e.0.delay();
+            let nanos = match delay.checked_duration_since(created) {
+                Some(ahead) => ahead.as_nanos() as i64,
+                _ => -((created - delay).as_nanos() as i64),
+            };
+            (nanos as u64).wrapping_add(HEAD_BIAS)
+        });
+        self.published.head.store(head, AtomicOrdering::Release);
+        self.published
+            .len
+            .store(state.heap.len(), AtomicOrdering::Release);
+        if let Some((limit, _)) = &self.soft_limit {
+            self.published
+                .under_pressure
+                .store(state.heap.len() >= *limit, AtomicOrdering::Release);
+        }
+    }
+
+    // pops the head if it can be released, discarding stale elements on the way
+    fn try_release<'a>(
+        &'a self,
+        state: MutexGuard<'a, State<T>>,
+        cutoff: Option<Instant>,
+    ) -> Released<'a, T> {
+        match self.pop_releasable(state, cutoff) {
+            Popped::Entry(state, e, now) => {
+                let seq = e.seq;
+                self.record_delivery(state, e.delay(), now);
+                Released::Item(seq, self.finish(e, Outcome::Delivered))
+            }
+            Popped::Pending(state, at) => Released::Pending(state, at),
+        }
+    }
+
+    // records the delivery of an element with the provided delay, then releases the lock before
+    // running the callbacks it triggers
+    fn record_delivery(&self, mut state: MutexGuard<State<T>>, delay: Instant, now: Instant) {
+        if let Some(interval) = self.pacing {
+            state.next_release = Some(now + interval);
+        }
+        state.record_take(now);
+        let late = self.lateness_crossed(&state, now, now.saturating_duration_since(delay));
+        let idle = self.is_idle(&state);
+        drop(state);
+        self.alert_lateness(late);
+        if idle {
+            self.notify_idle();
+        }
+    }
+
+    // whether this queue ran empty with no blocked producer about to refill it
+    fn is_idle(&self, state: &State<T>) -> bool {
+        state.heap.is_empty() && self.blocked_producers() == 0
+    }
+
+    fn notify_idle(&self) {
+        if let Some(on_idle) = &self.on_idle {
+            self.dispatch(on_idle);
+        }
+    }
+
+    // pops the head once it can be released, discarding stale elements on the way; with a cutoff
+    // only elements which could be released by then are popped
+    fn pop_releasable<'a>(
+        &'a self,
+        mut state: MutexGuard<'a, State<T>>,
+        cutoff: Option<Instant>,
+    ) -> Popped<'a, T> {
+        loop {
+            let now = self.now();
+            let release_at = state.heap.peek().map(|e| self.release_time(&state, &e.0));
+            let until = if self.tick_driven {
+                state.last_tick
+            } else {
+                Some(now)
+            };
+            let until = until.map(|until| cutoff.map_or(until, |cutoff| until.min(cutoff)));
+            match release_at {
+                Some(at) if until.is_some_and(|until| at <= until) => {
+                    let e = state.pop().unwrap();
+                    self.publish(&state);
+                    self.condvar.notify_all();
+                    if e.is_stale(now) {
+                        drop(state);
+                        self.discard_stale(e);
+                        state = self.state_mutex();
+                    } else {
+                        return Popped::Entry(state, e, now);
+                    }
+                }
+                // a tick-driven queue can't tell when the next tick happens
+                _ if self.tick_driven => return Popped::Pending(state, None),
+                _ => return Popped::Pending(state, release_at),
+            }
+        }
+    }
+
+    // updates whether the lateness bound is exceeded by the head or an element just delivered,
+    // returning the lateness if the bound was crossed
+    fn lateness_crossed(
+        &self,
+        state: &State<T>,
+        now: Instant,
+        delivered: Duration,
+    ) -> Option<Duration> {
+        let (bound, _) = self.lateness_alert.as_ref()?;
+        let head = state.heap.peek().map_or(Duration::ZERO, |e| {
+            now.saturating_duration_since(e.0.delay())
+        });
+        let lateness = head.max(delivered);
+        let late = lateness > *bound;
+        let was_late = self.late.swap(late, AtomicOrdering::AcqRel);
+        if late && !was_late {
+            Some(lateness)
+        } else {
+            None
+        }
+    }
+
+    fn alert_lateness(&self, lateness: Option<Duration>) {
+        if let (Some(lateness), Some((_, on_late))) = (lateness, &self.lateness_alert) {
+            self.dispatch(|| on_late(lateness));
+        }
+    }
+
+    // releases up to 'max' elements without waiting, returning the number of released elements
+    fn release_into<'a>(
+        &'a self,
+        state: MutexGuard<'a, State<T>>,
+        max: usize,
+        mut f: impl FnMut(T),
+    ) -> usize {
+        // elements expiring meanwhile are left for later, so this ends even under constant expiry
+        let cutoff = self.now();
+        let mut state = Some(state);
+        let mut released = 0;
+        while released < max {
+            let guard = state.take().unwrap_or_else(|| self.state_mutex());
+            match self.try_release(guard, Some(cutoff)) {
+                Released::Item(_, e) => {
+                    f(e);
+                    released += 1;
+                }
+                _ => break,
+            }
+        }
+        released
+    }
+
+    // notifies the listener of an element which left the queue, after the lock was released
+    fn finish(&self, e: Entry<T>, outcome: Outcome) -> T {
+        // only a listener is dispatched, so elements without one are released without allocating
+        let item = match e.listener {
+            Some(_) => self.dispatch(|| e.notify(outcome)),
+            _ => e.item,
+        };
+        #[cfg(feature = "audit")]
+        if let Some(audit) = &self.audit {
+            audit.record(audit.prepare(self.name(), AuditOperation::from(outcome), &item));
+        }
+        item
+    }
+
+    // runs a user callback of this queue, marked as such when checking for misuse
+    fn dispatch<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "debug-checks")]
+        return debug_checks::dispatch(self as *const Self as usize, f);
+        #[cfg(not(feature = "debug-checks"))]
+        f()
+    }
+
+    fn discard_stale(&self, e: Entry<T>) {
+        let item = self.finish(e, Outcome::Cancelled);
+        if let Some(on_stale) = &self.on_stale {
+            self.dispatch(|| on_stale(item));
+        }
+    }
+
+    fn can_accept_element(&self, state: &State<T>, e: &T) -> bool {
+        let has_space = self.capacity == 0 || state.heap.len() < self.capacity;
+        has_space && Self::within_quota(state, e)
+    }
+
+    fn within_quota(state: &State<T>, e: &T) -> bool {
+        state.quota.as_ref().is_none_or(|q| q.allows(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::mem::MaybeUninit;
+    use std::ops::Sub;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::{mpsc, Arc, Condvar, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::budgeted::Budgeted;
+    use crate::delay_item::{DelayItem, Delayed};
+    use crate::diagnostics::RECENT_OPERATIONS;
+    use crate::listener::Outcome;
+    use crate::memory::Eviction;
+
+    type MeasuredResult<T> = (T, Duration);
+
+    #[test]
+    fn should_put_and_take_ordered() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(DelayItem::new(1, Instant::now()));
+        queue.add(DelayItem::new(2, Instant::now()));
+
+        assert_eq!(1, queue.take().data);
+        assert_eq!(2, queue.take().data);
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_take_within_budget() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let deadline = Instant::now() + Duration::from_millis(30);
+        queue.add(DelayItem::new(1, deadline));
+
+        let (budgeted, elapsed) = measure_time_millis(|| {
+            queue
+                .take_budgeted(Duration::from_millis(5))
+                .map(|e| e.data)
+        });
+        assert_eq!(Budgeted::WouldBlockUntil(deadline), budgeted);
+        assert!(elapsed >= Duration::from_millis(5));
+        assert_eq!(1, queue.size());
+
+        let queue_rc = queue.clone();
+        let handle = thread::spawn(move || queue_rc.take_budgeted(Duration::from_secs(1)));
+        assert_eq!(Budgeted::Item(1), handle.join().unwrap().map(|e| e.data));
+        assert_eq!(
+            Budgeted::Empty,
+            queue.take_budgeted(Duration::ZERO).map(|e| e.data)
+        );
+    }
+
+    #[test]
+    fn should_put_and_take_delayed_items() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(DelayItem::new(
+            1,
+            Instant::now() + Duration::from_millis(10),
+        ));
+        queue.add(DelayItem::new(2, Instant::now()));
+
+        assert_eq!(2, queue.take().data);
+        assert_eq!(1, queue.take().data);
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_block_until_item_is_available_take() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let queue_rc = queue.clone();
+        let handle = thread::spawn(move || queue_rc.take());
+        queue.add(DelayItem::new(
+            1,
+            Instant::now() + Duration::from_millis(50),
+        ));
+        let res = handle.join().unwrap().data;
+        assert_eq!(1, res);
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_block_until_item_is_available_poll() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let queue_rc = queue.clone();
+        let handle = thread::spawn(move || queue_rc.poll(Duration::from_millis(10)));
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_millis(5)));
+        let res = handle.join().unwrap().unwrap().data;
+        assert_eq!(1, res);
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_block_until_item_can_be_added() {
+        let queue = Arc::new(BlockingDelayQueue::new_with_capacity(1));
+        queue.add(DelayItem::new(
+            1,
+            Instant::now() + Duration::from_millis(50),
+        ));
+        let queue_rc = queue.clone();
+        let handle = thread::spawn(move || queue_rc.add(DelayItem::new(2, Instant::now())));
+        assert_eq!(1, queue.take().data);
+        handle.join().unwrap();
+        assert_eq!(1, queue.size());
+        assert_eq!(2, queue.take().data);
+    }
+
+    #[test]
+    fn should_timeout_if_element_cant_be_added() {
+        let queue = BlockingDelayQueue::new_with_capacity(1);
+        let accepted = queue.offer(DelayItem::new(1, Instant::now()), Duration::from_millis(5));
+        // fill capacity
+        assert!(accepted);
+
+        // q is full here should block until timeout without inserting
+        let timeout = Duration::from_millis(50);
+        let res = measure_time_millis(|| queue.offer(DelayItem::new(2, Instant::now()), timeout));
+        // element is not accepted - timeout occurred
+        assert!(!res.0);
+        // timeout is respected with some delta
+        assert!(res.1 >= timeout && res.1.sub(timeout) <= Duration::from_millis(10));
+
+        assert_eq!(1, queue.take().data);
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_timeout_if_element_cant_be_polled() {
+        let queue: BlockingDelayQueue<DelayItem<u8>> = BlockingDelayQueue::new_unbounded();
+        let e = queue.poll(Duration::from_millis(5));
+        assert!(e.is_none());
+    }
+
+    #[test]
+    fn should_poll_as_soon_as_head_expires() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(DelayItem::new(
+            1,
+            Instant::now() + Duration::from_millis(20),
+        ));
+
+        let res = measure_time_millis(|| queue.poll(Duration::from_secs(5)));
+        assert_eq!(1, res.0.unwrap().data);
+        assert!(res.1 < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn should_return_only_expired_without_waiting() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(60)));
+        assert!(queue.expired().is_none());
+
+        queue.add(DelayItem::new(2, Instant::now()));
+        assert_eq!(2, queue.expired().unwrap().data);
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_report_scheduled_work() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(DelayItem::new(1, now));
+        queue.add(DelayItem::new(2, now + Duration::from_secs(30)));
+        queue.add(DelayItem::new(3, now + Duration::from_secs(90)));
+        queue.add(DelayItem::new(4, now + Duration::from_secs(7200)));
+
+        let minute = Duration::from_secs(60);
+        let hour = Duration::from_secs(3600);
+        assert_eq!(2, queue.count_due_within(minute));
+        assert_eq!(3, queue.count_due_within(hour));
+        assert_eq!(vec![2, 1, 1], queue.deadline_histogram(&[minute, hour]));
+        assert_eq!(vec![4], queue.deadline_histogram(&[]));
+        assert_eq!(4, queue.size());
+    }
+
+    #[test]
+    fn should_expose_items_in_delivery_order() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(DelayItem::new("c", now + Duration::from_secs(30)));
+        queue.add(DelayItem::new("a", now));
+        queue.add(DelayItem::new("b", now));
+
+        let view: Vec<_> = queue.with_items(|items| items.iter().map(|e| e.data).collect());
+        assert_eq!(vec!["a", "b", "c"], view);
+        assert_eq!(3, queue.size());
+    }
+
+    #[test]
+    fn should_add_only_absent_elements_concurrently() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let at = Instant::now() + Duration::from_secs(60);
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let queue_rc = queue.clone();
+                thread::spawn(move || {
+                    let order = i % 2;
+                    queue_rc.add_if_absent(|e| e.data == order, DelayItem::new(order, at))
+                })
+            })
+            .collect();
+
+        let added = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|added| *added)
+            .count();
+        assert_eq!(2, added);
+        assert_eq!(2, queue.size());
+    }
+
+    #[test]
+    fn should_find_and_count_matching_elements() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(DelayItem::new(
+            ("order-1", 3),
+            now + Duration::from_secs(30),
+        ));
+        queue.add(DelayItem::new(
+            ("order-2", 2),
+            now + Duration::from_secs(20),
+        ));
+        queue.add(DelayItem::new(
+            ("order-1", 1),
+            now + Duration::from_secs(10),
+        ));
+
+        assert_eq!(
+            Some(1),
+            queue.find_map_where(|e| e.data.0 == "order-1", |e| e.data.1)
+        );
+        assert_eq!(
+            None,
+            queue.find_map_where(|e| e.data.0 == "order-3", |e| e.data.1)
+        );
+        assert_eq!(2, queue.count_where(|e| e.data.0 == "order-1"));
+        assert_eq!(0, queue.count_where(|e| e.data.1 > 3));
+        assert_eq!(3, queue.size());
+    }
+
+    #[test]
+    fn should_publish_size_and_next_expiration() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        assert_eq!(None, queue.next_expiration());
+        let now = Instant::now();
+        let later = now + Duration::from_secs(60);
+        queue.add(DelayItem::new(2, later));
+        queue.add(DelayItem::new(1, now));
+
+        assert_eq!(Some(now), queue.next_expiration());
+        assert_eq!(2, queue.size());
+        assert_eq!(1, queue.take().data);
+        assert_eq!(Some(later), queue.next_expiration());
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(None, queue.next_expiration());
+    }
+
+    #[test]
+    fn should_report_aging_of_expired_items() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        assert_eq!(None, queue.oldest_expired_age());
+        let now = Instant::now();
+        queue.add(DelayItem::new(1, now - Duration::from_secs(10)));
+        queue.add(DelayItem::new(2, now - Duration::from_secs(30)));
+        queue.add(DelayItem::new(3, now + Duration::from_secs(60)));
+
+        assert_eq!(2, queue.expired_count());
+        let age = queue.oldest_expired_age().unwrap();
+        assert!(age >= Duration::from_secs(30) && age < Duration::from_secs(31));
+    }
+
+    #[test]
+    fn should_enforce_tenant_quota() {
+        let queue = BlockingDelayQueue::builder()
+            .quota(|e: &DelayItem<(&str, i32)>| e.data.0, 2)
+            .build();
+        assert!(queue
+            .try_add(DelayItem::new(("a", 1), Instant::now()))
+            .is_ok());
+        assert!(queue
+            .try_add(DelayItem::new(("a", 2), Instant::now()))
+            .is_ok());
+        assert!(queue
+            .try_add(DelayItem::new(("b", 3), Instant::now()))
+            .is_ok());
+
+        let rejected = queue.try_add(DelayItem::new(("a", 4), Instant::now()));
+        assert_eq!(4, rejected.unwrap_err().0.data.1);
+        assert!(!queue.offer(
+            DelayItem::new(("a", 5), Instant::now()),
+            Duration::from_millis(5)
+        ));
+
+        // taking an element of the tenant frees its quota
+        assert_eq!(1, queue.take().data.1);
+        assert!(queue
+            .try_add(DelayItem::new(("a", 6), Instant::now()))
+            .is_ok());
+        assert_eq!(3, queue.size());
+    }
+
+    #[test]
+    fn should_drop_stale_items() {
+        let (tx, rx) = mpsc::channel();
+        let queue = BlockingDelayQueue::builder()
+            .on_stale(move |e: DelayItem<i32>| tx.send(e.data).unwrap())
+            .build();
+        let now = Instant::now();
+        queue.add_valid_until(DelayItem::new(1, now), now);
+        queue.add_valid_until(DelayItem::new(2, now), now + Duration::from_secs(60));
+        thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(2, queue.take().data);
+        assert_eq!(1, rx.try_recv().unwrap());
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_take_batch_once_min_items_expired() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        queue.add(DelayItem::new(1, Instant::now()));
+        let queue_rc = queue.clone();
+        let handle = thread::spawn(move || queue_rc.take_at_least(3, 10, Duration::from_secs(5)));
+        queue.add(DelayItem::new(2, Instant::now()));
+        queue.add(DelayItem::new(
+            3,
+            Instant::now() + Duration::from_millis(20),
+        ));
+
+        let batch: Vec<_> = handle.join().unwrap().into_iter().map(|e| e.data).collect();
+        assert_eq!(vec![1, 2, 3], batch);
+    }
+
+    #[test]
+    fn should_take_partial_batch_on_timeout() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(DelayItem::new(1, Instant::now()));
+        queue.add(DelayItem::new(2, Instant::now()));
+        queue.add(DelayItem::new(3, Instant::now() + Duration::from_secs(60)));
+
+        assert_eq!(1, queue.take_at_least(1, 1, Duration::from_secs(5)).len());
+        assert_eq!(
+            1,
+            queue.take_at_least(5, 10, Duration::from_millis(10)).len()
+        );
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_count_only_releasable_items_for_batch() {
+        let queue = BlockingDelayQueue::builder().tick_driven().build();
+        queue.add(DelayItem::new(1, Instant::now()));
+        queue.add_valid_until(DelayItem::new(2, Instant::now()), Instant::now());
+        thread::sleep(Duration::from_millis(1));
+
+        assert!(queue
+            .take_at_least(1, 10, Duration::from_millis(10))
+            .is_empty());
+        queue.tick();
+        let batch: Vec<_> = queue
+            .take_at_least(2, 10, Duration::from_millis(10))
+            .into_iter()
+            .map(|e| e.data)
+            .collect();
+        assert_eq!(vec![1], batch);
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_append_expired_items_to_buffer() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        for i in 0..5 {
+            queue.add(DelayItem::new(i, now));
+        }
+        queue.add(DelayItem::new(9, now + Duration::from_secs(60)));
+
+        let mut buf = vec![DelayItem::new(-1, now)];
+        assert_eq!(3, queue.take_into(&mut buf, 3, Duration::from_millis(10)));
+        assert_eq!(2, queue.take_into(&mut buf, 3, Duration::from_millis(10)));
+        assert_eq!(0, queue.take_into(&mut buf, 3, Duration::from_millis(10)));
+        let data: Vec<_> = buf.iter().map(|e| e.data).collect();
+        assert_eq!(vec![-1, 0, 1, 2, 3, 4], data);
+        assert_eq!(0, queue.take_into(&mut buf, 0, Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn should_write_expired_items_into_slots() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        for i in 0..3 {
+            queue.add(DelayItem::new(i, now));
         }
+
+        let mut slots: [MaybeUninit<DelayItem<i32>>; 2] =
+            [MaybeUninit::uninit(), MaybeUninit::uninit()];
+        assert_eq!(
+            2,
+            queue.take_into_slice(&mut slots, Duration::from_millis(10))
+        );
+        let taken: Vec<_> = slots
+            .iter()
+            .map(|s| unsafe { s.assume_init_read() }.data)
+            .collect();
+        assert_eq!(vec![0, 1], taken);
+        assert_eq!(
+            1,
+            queue.take_into_slice(&mut slots, Duration::from_millis(10))
+        );
+        assert_eq!(2, unsafe { slots[0].assume_init_read() }.data);
+        assert_eq!(
+            0,
+            queue.take_into_slice(&mut slots, Duration::from_millis(10))
+        );
+        assert_eq!(0, queue.take_into_slice(&mut [], Duration::from_secs(1)));
     }
 
-    fn is_expired(e: &T) -> bool {
-        e.delay() <= Instant::now()
+    #[test]
+    fn should_stamp_sequence_numbers() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(DelayItem::new(1, now + Duration::from_millis(10)));
+        queue.add(DelayItem::new(2, now));
+        queue.add(DelayItem::new(3, now));
+
+        assert_eq!((1, 2), map_data(queue.take_with_seq()));
+        assert_eq!((2, 3), map_data(queue.take_with_seq()));
+        assert_eq!(
+            Some((0, 1)),
+            queue.poll_with_seq(Duration::from_secs(1)).map(map_data)
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::ops::Sub;
-    use std::sync::{Arc, Condvar, Mutex};
-    use std::thread;
-    use std::time::{Duration, Instant};
+    #[test]
+    fn should_evict_over_memory_limit() {
+        let (tx, rx) = mpsc::channel();
+        let queue = BlockingDelayQueue::builder()
+            .memory_limit(
+                10,
+                |e: &DelayItem<&str>| e.data.len(),
+                Eviction::FurthestDeadline,
+            )
+            .on_evict(move |e| tx.send(e.data).unwrap())
+            .build();
+        let now = Instant::now();
+        queue.add(DelayItem::new("near", now));
+        queue.add(DelayItem::new("far", now + Duration::from_secs(60)));
+        queue.add(DelayItem::new("mid", now + Duration::from_secs(30)));
 
-    use crate::blocking_delay_queue::BlockingDelayQueue;
-    use crate::delay_item::DelayItem;
+        // 4 + 3 + 3 bytes fit, the next insertion evicts the furthest deadline
+        assert_eq!(3, queue.size());
+        queue.add(DelayItem::new("new", now + Duration::from_secs(1)));
+        assert_eq!("far", rx.try_recv().unwrap());
+        assert_eq!(3, queue.size());
+        assert_eq!("near", queue.take().data);
+        assert!(rx.try_recv().is_err());
+    }
 
-    type MeasuredResult<T> = (T, Duration);
+    #[test]
+    fn should_evict_until_back_under_memory_limit() {
+        let (tx, rx) = mpsc::channel();
+        let queue = BlockingDelayQueue::builder()
+            .memory_limit(
+                10,
+                |e: &DelayItem<&str>| e.data.len(),
+                Eviction::FurthestDeadline,
+            )
+            .on_evict(move |e| tx.send(e.data).unwrap())
+            .build();
+        let now = Instant::now();
+        queue.add(DelayItem::new("near", now));
+        queue.add(DelayItem::new("far", now + Duration::from_secs(60)));
+        queue.add(DelayItem::new("mid", now + Duration::from_secs(30)));
+
+        // 4 + 3 + 3 + 5 bytes, two evictions are needed to get back to 10
+        queue.add(DelayItem::new("large", now + Duration::from_secs(1)));
+        assert_eq!(vec!["far", "mid"], rx.try_iter().collect::<Vec<_>>());
+        assert_eq!(2, queue.size());
+        assert_eq!("near", queue.take().data);
+        assert_eq!("large", queue.take().data);
+    }
 
     #[test]
-    fn should_put_and_take_ordered() {
+    fn should_cancel_all_items_of_scope() {
+        let queue = BlockingDelayQueue::new_with_capacity(3);
+        let now = Instant::now();
+        let session = queue.cancel_scope();
+        let other = queue.cancel_scope();
+        queue.add_scoped(&session, DelayItem::new(1, now));
+        queue.add_scoped(&other, DelayItem::new(2, now));
+        queue.add_scoped(&session, DelayItem::new(3, now));
+
+        assert_eq!(2, session.cancel_all());
+        assert_eq!(0, session.cancel_all());
+        queue.add(DelayItem::new(4, now));
+        assert_eq!(vec![2, 4], vec![queue.take().data, queue.take().data]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cancel scope belongs to another queue")]
+    fn should_reject_scope_of_other_queue() {
         let queue = BlockingDelayQueue::new_unbounded();
-        queue.add(DelayItem::new(1, Instant::now()));
-        queue.add(DelayItem::new(2, Instant::now()));
+        let other = BlockingDelayQueue::new_unbounded();
+        queue.add_scoped(&other.cancel_scope(), DelayItem::new(1, Instant::now()));
+    }
 
-        assert_eq!(1, queue.take().data);
-        assert_eq!(2, queue.take().data);
-        assert_eq!(0, queue.size());
+    #[test]
+    fn should_recheck_deadlines_every_wait_slice() {
+        struct Shifting(Arc<Mutex<Instant>>);
+        impl Delayed for Shifting {
+            fn delay(&self) -> Instant {
+                *self.0.lock().unwrap()
+            }
+        }
+        impl Ord for Shifting {
+            fn cmp(&self, _: &Self) -> Ordering {
+                Ordering::Equal
+            }
+        }
+        impl PartialOrd for Shifting {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl PartialEq for Shifting {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+        impl Eq for Shifting {}
+
+        // simulates a clock jump the queue isn't notified about, like a wake from suspend
+        let due = Arc::new(Mutex::new(Instant::now() + Duration::from_secs(60)));
+        let queue = BlockingDelayQueue::builder()
+            .max_wait_slice(Duration::from_millis(10))
+            .build();
+        queue.add(Shifting(due.clone()));
+        let shift = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            *due.lock().unwrap() = Instant::now();
+        });
+
+        let res = measure_time_millis(|| queue.poll(Duration::from_secs(5)));
+        assert!(res.0.is_some());
+        assert!(res.1 < Duration::from_secs(1));
+        shift.join().unwrap();
+    }
+
+    // counts the allocations of the current thread while it's measuring
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|a| a.set(a.get().map(|n| n + 1)));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+        ALLOCATIONS.with(|a| a.set(Some(0)));
+        let r = f();
+        (r, ALLOCATIONS.with(|a| a.take()).unwrap())
     }
 
     #[test]
-    fn should_put_and_take_delayed_items() {
+    fn should_take_into_slice_without_allocating() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        for i in 0..(RECENT_OPERATIONS as u32 + 2) {
+            queue.add(DelayItem::new(i, now));
+        }
+        let mut slots: [MaybeUninit<DelayItem<u32>>; 4] = [
+            MaybeUninit::uninit(),
+            MaybeUninit::uninit(),
+            MaybeUninit::uninit(),
+            MaybeUninit::uninit(),
+        ];
+
+        let (first, allocations) =
+            count_allocations(|| queue.take_into_slice(&mut slots, Duration::ZERO));
+        assert_eq!((4, 0), (first, allocations));
+        let (rest, allocations) =
+            count_allocations(|| queue.take_into_slice(&mut slots, Duration::from_secs(1)));
+        assert_eq!((4, 0), (rest, allocations));
+        assert_eq!(2, queue.size());
+    }
+
+    #[test]
+    fn should_accept_timeouts_too_long_to_represent() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        assert!(queue.offer(DelayItem::new(1, now), Duration::MAX));
+        assert!(queue.offer(DelayItem::new(2, now), Duration::MAX));
+        assert!(queue.offer(DelayItem::new(3, now), Duration::MAX));
+        assert!(queue.offer(DelayItem::new(4, now), Duration::MAX));
+        queue.add(DelayItem::new(5, now));
+        assert_eq!(5, queue.count_due_within(Duration::MAX));
+
+        assert_eq!(Some(1), queue.poll(Duration::MAX).map(|e| e.data));
+        let batch = queue.take_at_least(1, 1, Duration::MAX);
+        assert_eq!(
+            vec![2],
+            batch.into_iter().map(|e| e.data).collect::<Vec<_>>()
+        );
+        let mut buf = Vec::new();
+        assert_eq!(1, queue.take_into(&mut buf, 1, Duration::MAX));
+        let mut slots = [MaybeUninit::uninit()];
+        assert_eq!(1, queue.take_into_slice(&mut slots, Duration::MAX));
+        assert!(matches!(
+            queue.take_budgeted(Duration::MAX),
+            Budgeted::Item(DelayItem { data: 5, .. })
+        ));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn should_oversleep_clock_jump_without_wait_slice() {
+        struct Shifting(Arc<Mutex<Instant>>);
+        impl Delayed for Shifting {
+            fn delay(&self) -> Instant {
+                *self.0.lock().unwrap()
+            }
+        }
+        impl Ord for Shifting {
+            fn cmp(&self, _: &Self) -> Ordering {
+                Ordering::Equal
+            }
+        }
+        impl PartialOrd for Shifting {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+        impl PartialEq for Shifting {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+        impl Eq for Shifting {}
+
+        // the same unnotified jump is only noticed once the poll timeout expires
+        let due = Arc::new(Mutex::new(Instant::now() + Duration::from_secs(60)));
         let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(Shifting(due.clone()));
+        let shift = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            *due.lock().unwrap() = Instant::now();
+        });
+
+        let res = measure_time_millis(|| queue.poll(Duration::from_millis(200)));
+        assert!(res.0.is_some());
+        assert!(res.1 >= Duration::from_millis(200));
+        shift.join().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "test-util")]
+    fn should_notice_clock_resume_within_wait_slice() {
+        let clock = crate::test_util::MockClock::new();
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .clock(&clock)
+                .max_wait_slice(Duration::from_millis(10))
+                .build(),
+        );
         queue.add(DelayItem::new(
-            1,
-            Instant::now() + Duration::from_millis(10),
+            "due",
+            clock.now() + Duration::from_secs(3600),
         ));
-        queue.add(DelayItem::new(2, Instant::now()));
+        let queue_rc = queue.clone();
+        let consumer = thread::spawn(move || queue_rc.take());
+        thread::sleep(Duration::from_millis(20));
+
+        // a resume from suspend moves the clock without waking the consumer
+        let resumed = Instant::now();
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!("due", consumer.join().unwrap().data);
+        assert!(resumed.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn should_spread_identical_deadlines_with_jitter() {
+        let queue = BlockingDelayQueue::builder()
+            .jitter(Duration::from_secs(1800))
+            .build();
+        let at = Instant::now() + Duration::from_secs(3600);
+        for i in 0..100 {
+            queue.add(DelayItem::new(i, at));
+        }
+
+        let early = queue.count_due_within(Duration::from_secs(3600));
+        assert!(early > 0 && early < 100);
+        assert_eq!(0, queue.count_due_within(Duration::from_secs(1700)));
+        assert_eq!(100, queue.count_due_within(Duration::from_secs(5500)));
+    }
+
+    #[test]
+    fn should_spin_until_deadline_when_waking_early() {
+        let queue = BlockingDelayQueue::builder()
+            .early_wake(Duration::from_millis(5))
+            .build();
+        let deadline = Instant::now() + Duration::from_millis(20);
+        queue.add(DelayItem::new(1, deadline));
 
-        assert_eq!(2, queue.take().data);
         assert_eq!(1, queue.take().data);
-        assert_eq!(0, queue.size());
+        let now = Instant::now();
+        assert!(now >= deadline);
+        assert!(now - deadline < Duration::from_millis(10));
     }
 
+    // CPU time consumed by the calling thread
+    #[cfg(target_os = "linux")]
+    fn thread_cpu_time() -> Duration {
+        let schedstat = std::fs::read_to_string("/proc/thread-self/schedstat").unwrap();
+        Duration::from_nanos(schedstat.split(' ').next().unwrap().parse().unwrap())
+    }
+
+    #[cfg(target_os = "linux")]
     #[test]
-    fn should_block_until_item_is_available_take() {
-        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    fn should_spin_only_for_the_heads_release_time() {
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .capacity(1)
+                .early_wake(Duration::from_secs(10))
+                .build(),
+        );
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(5)));
+
         let queue_rc = queue.clone();
-        let handle = thread::spawn(move || queue_rc.take());
-        queue.add(DelayItem::new(
-            1,
-            Instant::now() + Duration::from_millis(50),
-        ));
-        let res = handle.join().unwrap().data;
-        assert_eq!(1, res);
-        assert_eq!(0, queue.size());
+        let producer = thread::spawn(move || {
+            let cpu = thread_cpu_time();
+            let added = queue_rc.offer(
+                DelayItem::new(2, Instant::now()),
+                Duration::from_millis(200),
+            );
+            (added, thread_cpu_time() - cpu)
+        });
+        let queue_rc = queue.clone();
+        let consumer = thread::spawn(move || {
+            let cpu = thread_cpu_time();
+            let polled = queue_rc.poll(Duration::from_millis(200));
+            (polled.is_none(), thread_cpu_time() - cpu)
+        });
+
+        let (added, producer_cpu) = producer.join().unwrap();
+        let (timed_out, consumer_cpu) = consumer.join().unwrap();
+        assert!(!added && timed_out);
+        assert!(producer_cpu < Duration::from_millis(50));
+        assert!(consumer_cpu < Duration::from_millis(50));
     }
 
     #[test]
-    fn should_block_until_item_is_available_poll() {
-        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    fn should_stop_spinning_when_the_head_changes() {
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .early_wake(Duration::from_secs(10))
+                .build(),
+        );
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(5)));
         let queue_rc = queue.clone();
-        let handle = thread::spawn(move || queue_rc.poll(Duration::from_millis(10)));
-        queue.add(DelayItem::new(1, Instant::now() + Duration::from_millis(5)));
-        let res = handle.join().unwrap().unwrap().data;
-        assert_eq!(1, res);
-        assert_eq!(0, queue.size());
+        let consumer = thread::spawn(move || queue_rc.take());
+        thread::sleep(Duration::from_millis(20));
+
+        let start = Instant::now();
+        queue.add(DelayItem::new(2, Instant::now()));
+        assert_eq!(2, consumer.join().unwrap().data);
+        assert!(start.elapsed() < Duration::from_secs(1));
     }
 
     #[test]
-    fn should_block_until_item_can_be_added() {
+    fn should_warn_once_per_soft_limit_crossing() {
+        let (tx, rx) = mpsc::channel();
+        let queue = BlockingDelayQueue::builder()
+            .soft_limit(2, move |size| tx.send(size).unwrap())
+            .build();
+        for i in 0..3 {
+            queue.add(DelayItem::new(i, Instant::now()));
+        }
+        assert!(queue.is_under_pressure());
+        assert_eq!(vec![2], rx.try_iter().collect::<Vec<_>>());
+
+        queue.take();
+        queue.take();
+        assert!(!queue.is_under_pressure());
+        queue.add(DelayItem::new(3, Instant::now()));
+        assert_eq!(Ok(2), rx.try_recv());
+    }
+
+    #[test]
+    fn should_alert_once_when_consumers_fall_behind() {
+        let (tx, rx) = mpsc::channel();
+        let queue = BlockingDelayQueue::builder()
+            .lateness_alert(Duration::from_millis(10), move |lateness| {
+                tx.send(lateness).unwrap()
+            })
+            .build();
+        queue.add(DelayItem::new(1, Instant::now()));
+        thread::sleep(Duration::from_millis(20));
+        // a producer notices the stalled consumer
+        queue.add(DelayItem::new(2, Instant::now()));
+        queue.add(DelayItem::new(3, Instant::now()));
+        assert!(rx.try_recv().unwrap() >= Duration::from_millis(10));
+        assert!(rx.try_recv().is_err());
+
+        for _ in 0..3 {
+            queue.take();
+        }
+        queue.add(DelayItem::new(4, Instant::now()));
+        thread::sleep(Duration::from_millis(20));
+        queue.take();
+        assert!(rx.try_recv().unwrap() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn should_use_static_queue() {
+        static QUEUE: BlockingDelayQueue<DelayItem<u8>> = BlockingDelayQueue::new_unbounded();
+        let at = Instant::now() + Duration::from_secs(60);
+        QUEUE.add(DelayItem::new(1, at));
+        assert_eq!(Some(at), QUEUE.next_expiration());
+        assert_eq!(1, QUEUE.size());
+        QUEUE.clear();
+        assert_eq!(None, QUEUE.next_expiration());
+    }
+
+    #[test]
+    fn should_name_queue_in_poisoned_lock_panics() {
+        let queue = Arc::new(
+            BlockingDelayQueue::<DelayItem<u8>>::builder()
+                .name("orders")
+                .build(),
+        );
+        let queue_rc = queue.clone();
+        let _ = thread::spawn(move || {
+            let _state = queue_rc.state_mutex();
+            panic!("poisoning the queue lock");
+        })
+        .join();
+
+        let panic = panic::catch_unwind(AssertUnwindSafe(|| queue.clear())).unwrap_err();
+        assert_eq!(
+            Some(&"Queue 'orders' lock poisoned".to_string()),
+            panic.downcast_ref::<String>()
+        );
+    }
+
+    #[test]
+    fn should_count_blocked_producers_and_consumers() {
         let queue = Arc::new(BlockingDelayQueue::new_with_capacity(1));
-        queue.add(DelayItem::new(
-            1,
-            Instant::now() + Duration::from_millis(50),
-        ));
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let queue_rc = queue.clone();
+                thread::spawn(move || queue_rc.poll(Duration::from_millis(100)))
+            })
+            .collect();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            (0, 2),
+            (queue.blocked_producers(), queue.blocked_consumers())
+        );
+        consumers
+            .into_iter()
+            .for_each(|c| assert!(c.join().unwrap().is_none()));
+
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(60)));
         let queue_rc = queue.clone();
-        let handle = thread::spawn(move || queue_rc.add(DelayItem::new(2, Instant::now())));
+        let producer = thread::spawn(move || {
+            queue_rc.offer(
+                DelayItem::new(2, Instant::now()),
+                Duration::from_millis(100),
+            )
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            (1, 0),
+            (queue.blocked_producers(), queue.blocked_consumers())
+        );
+        assert!(!producer.join().unwrap());
+        assert_eq!(0, queue.blocked_producers());
+    }
+
+    #[test]
+    fn should_report_idle_once_all_elements_are_taken() {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .capacity(1)
+                .on_idle(move || tx.lock().unwrap().send(()).unwrap())
+                .build(),
+        );
+        queue.add(DelayItem::new(1, Instant::now()));
+        let queue_rc = queue.clone();
+        let producer = thread::spawn(move || queue_rc.add(DelayItem::new(2, Instant::now())));
+        thread::sleep(Duration::from_millis(20));
+
+        let queue_rc = queue.clone();
+        let waiter = thread::spawn(move || queue_rc.wait_idle());
         assert_eq!(1, queue.take().data);
-        handle.join().unwrap();
-        assert_eq!(1, queue.size());
+        producer.join().unwrap();
+        assert!(rx.try_recv().is_err());
+        assert!(!waiter.is_finished());
+
         assert_eq!(2, queue.take().data);
+        waiter.join().unwrap();
+        assert!(rx.try_recv().is_ok());
     }
 
     #[test]
-    fn should_timeout_if_element_cant_be_added() {
-        let queue = BlockingDelayQueue::new_with_capacity(1);
-        let accepted = queue.offer(DelayItem::new(1, Instant::now()), Duration::from_millis(5));
-        // fill capacity
-        assert!(accepted);
+    fn should_release_only_on_ticks() {
+        let queue = Arc::new(BlockingDelayQueue::builder().tick_driven().build());
+        let queue_rc = queue.clone();
+        let consumer = thread::spawn(move || queue_rc.take());
+        queue.add(DelayItem::new(1, Instant::now()));
+        queue.add(DelayItem::new(
+            2,
+            Instant::now() + Duration::from_millis(10),
+        ));
+        thread::sleep(Duration::from_millis(20));
+        assert!(!consumer.is_finished());
+        assert_eq!(2, queue.size());
 
-        // q is full here should block until timeout without inserting
-        let timeout = Duration::from_millis(50);
-        let res = measure_time_millis(|| queue.offer(DelayItem::new(2, Instant::now()), timeout));
-        // element is not accepted - timeout occurred
-        assert!(!res.0);
-        // timeout is respected with some delta
-        assert!(res.1 >= timeout && res.1.sub(timeout) <= Duration::from_millis(10));
+        queue.add(DelayItem::new(3, Instant::now() + Duration::from_secs(60)));
+        queue.tick();
+        assert_eq!(1, consumer.join().unwrap().data);
+        assert_eq!(2, queue.expired().unwrap().data);
+        assert!(queue.poll(Duration::from_millis(10)).is_none());
+        assert_eq!(1, queue.size());
+    }
 
-        assert_eq!(1, queue.take().data);
-        assert_eq!(0, queue.size());
+    #[test]
+    fn should_report_idle_once_cleared() {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .on_idle(move || tx.lock().unwrap().send(()).unwrap())
+                .build(),
+        );
+        queue.clear();
+        assert!(rx.try_recv().is_err());
+
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(60)));
+        let queue_rc = queue.clone();
+        let waiter = thread::spawn(move || queue_rc.wait_idle());
+        queue.clear();
+        waiter.join().unwrap();
+        assert!(rx.try_recv().is_ok());
     }
 
     #[test]
-    fn should_timeout_if_element_cant_be_polled() {
-        let queue: BlockingDelayQueue<DelayItem<u8>> = BlockingDelayQueue::new_unbounded();
-        let e = queue.poll(Duration::from_millis(5));
-        assert!(e.is_none());
+    fn should_report_idle_once_last_scope_is_cancelled() {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .on_idle(move || tx.lock().unwrap().send(()).unwrap())
+                .build(),
+        );
+        let later = Instant::now() + Duration::from_secs(60);
+        let session = queue.cancel_scope();
+        let other = queue.cancel_scope();
+        queue.add_scoped(&session, DelayItem::new(1, later));
+        queue.add_scoped(&other, DelayItem::new(2, later));
+
+        assert_eq!(1, session.cancel_all());
+        assert!(rx.try_recv().is_err());
+        let queue_rc = queue.clone();
+        let waiter = thread::spawn(move || queue_rc.wait_idle());
+        assert_eq!(1, other.cancel_all());
+        waiter.join().unwrap();
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn should_capture_diagnostics() {
+        let queue = Arc::new(BlockingDelayQueue::new_with_capacity(1));
+        let consumer_rc = queue.clone();
+        let consumer = thread::spawn(move || consumer_rc.take());
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(1, queue.diagnostics().blocked_consumers);
+        queue.add(DelayItem::new(0, Instant::now()));
+        assert_eq!(0, consumer.join().unwrap().data);
+
+        queue.add(DelayItem::new(1, Instant::now() + Duration::from_secs(60)));
+        let producer_rc = queue.clone();
+        let producer = thread::spawn(move || producer_rc.add(DelayItem::new(2, Instant::now())));
+        thread::sleep(Duration::from_millis(20));
+        let diagnostics = queue.diagnostics();
+        assert_eq!((1, 0), (diagnostics.pending, diagnostics.expired));
+        assert_eq!(
+            (1, 0),
+            (diagnostics.blocked_producers, diagnostics.blocked_consumers)
+        );
+        assert_eq!(
+            (2, 1),
+            (diagnostics.last_adds.len(), diagnostics.last_takes.len())
+        );
+        queue.clear();
+        producer.join().unwrap();
+
+        for _ in 0..10 {
+            queue.take();
+            queue.add(DelayItem::new(3, Instant::now()));
+        }
+        let diagnostics = queue.diagnostics();
+        assert_eq!(
+            (8, 8),
+            (diagnostics.last_adds.len(), diagnostics.last_takes.len())
+        );
+        assert!(diagnostics.last_adds.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn should_quarantine_elements_panicking_on_add() {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct Job(Option<Instant>);
+
+        impl Delayed for Job {
+            fn delay(&self) -> Instant {
+                self.0.expect("job without a deadline")
+            }
+        }
+
+        let queue = BlockingDelayQueue::new_unbounded();
+        queue.add(Job(Some(Instant::now())));
+        queue.add(Job(None));
+        assert!(!queue.offer(Job(None), Duration::from_millis(1)));
+
+        assert_eq!(1, queue.size());
+        assert_eq!(vec![Job(None), Job(None)], queue.drain_quarantined());
+        assert!(queue.take().0.is_some());
+        assert!(queue.drain_quarantined().is_empty());
+    }
+
+    #[test]
+    fn should_quarantine_elements_panicking_while_ordered() {
+        // compares normally, except that 99 can't be ordered against 2
+        #[derive(Debug)]
+        struct Job(u32, Instant);
+
+        impl Delayed for Job {
+            fn delay(&self) -> Instant {
+                self.1
+            }
+        }
+
+        impl Ord for Job {
+            fn cmp(&self, other: &Self) -> Ordering {
+                if self.0.min(other.0) == 2 && self.0.max(other.0) == 99 {
+                    panic!("{} and {} can't be ordered", self.0, other.0);
+                }
+                self.0.cmp(&other.0)
+            }
+        }
+
+        impl PartialOrd for Job {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl PartialEq for Job {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for Job {}
+
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        for i in 1..=3 {
+            queue.add(Job(i, now));
+        }
+
+        // ordered against 2, which isn't the head
+        assert!(!queue.add_if_absent(|e| e.0 == 99, Job(99, now)));
+        assert_eq!(3, queue.size());
+        assert_eq!(Some(now), queue.next_expiration());
+        let quarantined: Vec<_> = queue.drain_quarantined().iter().map(|e| e.0).collect();
+        assert_eq!(vec![99], quarantined);
+        let taken: Vec<_> = (0..3).map(|_| queue.take().0).collect();
+        assert_eq!(vec![1, 2, 3], taken);
     }
 
     #[test]
@@ -440,10 +3068,52 @@ mod tests {
         thread::park_timeout(Duration::from_secs(1));
         thread::sleep(Duration::from_millis(50));
         // Check if the thread returned
-        assert_eq!(true, handler.is_finished());
+        assert!(handler.is_finished());
         handler.join().expect("failed to join thread");
     }
 
+    #[test]
+    fn should_notify_listener_on_delivery_and_clear() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let (tx, rx) = mpsc::channel();
+        let delivered_tx = tx.clone();
+        queue.add_with_listener(DelayItem::new(1, Instant::now()), move |o| {
+            delivered_tx.send((1, o)).unwrap()
+        });
+        queue.add_with_listener(DelayItem::new(2, Instant::now()), move |o| {
+            tx.send((2, o)).unwrap()
+        });
+
+        assert_eq!(1, queue.take().data);
+        assert_eq!((1, Outcome::Delivered), rx.try_recv().unwrap());
+        queue.clear();
+        assert_eq!((2, Outcome::Cancelled), rx.try_recv().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "debug-checks")]
+    #[should_panic(expected = "Blocking add to full queue 'orders' from one of its own callbacks")]
+    fn should_panic_on_blocking_add_from_own_callback() {
+        let queue = Arc::new(
+            BlockingDelayQueue::builder()
+                .capacity(2)
+                .name("orders")
+                .build(),
+        );
+        let in_listener = queue.clone();
+        queue.add_with_listener(DelayItem::new(1, Instant::now()), move |_| {
+            in_listener.add(DelayItem::new(3, Instant::now()));
+            in_listener.add(DelayItem::new(4, Instant::now()));
+        });
+        queue.add(DelayItem::new(2, Instant::now() + Duration::from_secs(60)));
+
+        queue.take();
+    }
+
+    fn map_data<T>(e: (u64, DelayItem<T>)) -> (u64, T) {
+        (e.0, e.1.data)
+    }
+
     fn measure_time_millis<T>(f: impl Fn() -> T) -> MeasuredResult<T> {
         let now = Instant::now();
         let t = f();
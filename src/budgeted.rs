@@ -0,0 +1,32 @@
+use std::time::Instant;
+
+/// The result of a time-sliced take, see
+/// [take_budgeted](crate::BlockingDelayQueue::take_budgeted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budgeted<T> {
+    /// An element expired within the time slice.
+    Item(T),
+    /// No element expired within the time slice, the head of the queue can be released at the
+    /// provided time unless the queue changes in the meantime.
+    WouldBlockUntil(Instant),
+    /// The queue stayed empty for the whole time slice.
+    Empty,
+}
+
+impl<T> Budgeted<T> {
+    /// Maps the element of an [Item](Budgeted::Item) result, keeping the other results unchanged.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::Budgeted;
+    /// assert_eq!(Budgeted::Item(2), Budgeted::Item(1).map(|i| i + 1));
+    /// ```
+    pub fn map<R>(self, f: impl FnOnce(T) -> R) -> Budgeted<R> {
+        match self {
+            Budgeted::Item(e) => Budgeted::Item(f(e)),
+            Budgeted::WouldBlockUntil(at) => Budgeted::WouldBlockUntil(at),
+            Budgeted::Empty => Budgeted::Empty,
+        }
+    }
+}
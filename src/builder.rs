@@ -0,0 +1,608 @@
+use std::hash::Hash;
+use std::time::Duration;
+
+#[cfg(feature = "audit")]
+use crate::audit::{AuditSink, Auditor};
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::config::QueueConfig;
+use crate::delay_item::Delayed;
+use crate::memory::{Eviction, MemoryLimit};
+use crate::quota::{KeyedQuota, Quota};
+#[cfg(feature = "test-util")]
+use crate::test_util::MockClock;
+
+pub(crate) type PressureCallback = Box<dyn Fn(usize) + Send + Sync>;
+pub(crate) type LatenessCallback = Box<dyn Fn(Duration) + Send + Sync>;
+
+/// A builder for [BlockingDelayQueue](crate::BlockingDelayQueue) combining construction options
+/// which would otherwise require dedicated constructors, see
+/// [builder](crate::BlockingDelayQueue::builder).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+///     .capacity(1024)
+///     .build();
+/// ```
+pub struct QueueBuilder<T> {
+    pub(crate) name: Option<String>,
+    pub(crate) capacity: usize,
+    pub(crate) pacing: Option<Duration>,
+    pub(crate) tick_driven: bool,
+    pub(crate) quota: Option<Box<dyn Quota<T> + Send>>,
+    pub(crate) on_stale: Option<Box<dyn Fn(T) + Send + Sync>>,
+    pub(crate) memory: Option<MemoryLimit<T>>,
+    pub(crate) on_evict: Option<Box<dyn Fn(T) + Send + Sync>>,
+    pub(crate) soft_limit: Option<(usize, PressureCallback)>,
+    pub(crate) lateness_alert: Option<(Duration, LatenessCallback)>,
+    pub(crate) on_idle: Option<Box<dyn Fn() + Send + Sync>>,
+    pub(crate) default_offer_timeout: Option<Duration>,
+    pub(crate) default_poll_timeout: Option<Duration>,
+    pub(crate) max_wait_slice: Option<Duration>,
+    pub(crate) early_wake: Option<Duration>,
+    pub(crate) jitter: Option<Duration>,
+    #[cfg(feature = "audit")]
+    pub(crate) audit: Option<Auditor<T>>,
+    #[cfg(feature = "test-util")]
+    pub(crate) clock: Option<MockClock>,
+}
+
+impl<T> Default for QueueBuilder<T>
+where
+    T: Delayed + Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> QueueBuilder<T>
+where
+    T: Delayed + Ord,
+{
+    /// Creates a new builder of an unbounded queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, QueueBuilder};
+    /// let queue = QueueBuilder::<DelayItem<&str>>::new().build();
+    /// ```
+    pub fn new() -> Self {
+        QueueBuilder {
+            name: None,
+            capacity: 0,
+            pacing: None,
+            tick_driven: false,
+            quota: None,
+            on_stale: None,
+            memory: None,
+            on_evict: None,
+            soft_limit: None,
+            lateness_alert: None,
+            on_idle: None,
+            default_offer_timeout: None,
+            default_poll_timeout: None,
+            max_wait_slice: None,
+            early_wake: None,
+            jitter: None,
+            #[cfg(feature = "audit")]
+            audit: None,
+            #[cfg(feature = "test-util")]
+            clock: None,
+        }
+    }
+
+    /// Names the queue so multi-queue applications can tell which queue an event came from.
+    /// The name is included in audit records, in panic messages of the queue and in the names
+    /// of threads spawned for it, e.g. 'orders-pump'.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder().name("orders").build();
+    /// ```
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the queue capacity where '0' is treated as unbounded.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder().capacity(4).build();
+    /// ```
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Limits delivery to at most the provided number of elements per second, spacing
+    /// consecutive deliveries evenly regardless of how many elements are already expired.
+    /// '0' disables pacing, which is the default.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .max_delivery_rate(100)
+    ///     .build();
+    /// ```
+    pub fn max_delivery_rate(mut self, per_second: u32) -> Self {
+        self.pacing = match per_second {
+            0 => None,
+            _ => Some(Duration::from_secs(1) / per_second),
+        };
+        self
+    }
+
+    /// Aligns deliveries to an external loop, e.g. a frame or poll loop: elements are only
+    /// released by [tick](crate::BlockingDelayQueue::tick), which releases the elements whose
+    /// delay expired by the time of the tick, instead of as soon as their delay expires.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .tick_driven()
+    ///     .build();
+    /// ```
+    pub fn tick_driven(mut self) -> Self {
+        self.tick_driven = true;
+        self
+    }
+
+    /// Limits the number of pending elements per tenant, where the tenant of an element is
+    /// determined by the provided key extractor.
+    /// Producers of a tenant at its limit wait in ['add'] / ['offer'] like on a full queue, while
+    /// [try_add](crate::BlockingDelayQueue::try_add) rejects the element with
+    /// [QuotaExceeded](crate::QuotaExceeded).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<(&str, u64)>>::builder()
+    ///     .capacity(1024)
+    ///     .quota(|e| e.data.0, 128)
+    ///     .build();
+    /// ```
+    pub fn quota<K>(mut self, key: impl Fn(&T) -> K + Send + 'static, max_pending: usize) -> Self
+    where
+        T: 'static,
+        K: Eq + Hash + Send + 'static,
+    {
+        self.quota = Some(Box::new(KeyedQuota::new(key, max_pending)));
+        self
+    }
+
+    /// Sets a callback receiving elements which were dropped instead of delivered because they
+    /// weren't taken in time, see [add_valid_until](crate::BlockingDelayQueue::add_valid_until).
+    /// The callback runs on the consumer thread which discovered the stale element.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::builder()
+    ///     .on_stale(|e: DelayItem<&str>| println!("{} expired unseen", e.data))
+    ///     .build();
+    /// ```
+    pub fn on_stale(mut self, on_stale: impl Fn(T) + Send + Sync + 'static) -> Self {
+        self.on_stale = Some(Box::new(on_stale));
+        self
+    }
+
+    /// Limits the estimated memory used by queued elements, where the size of each element is
+    /// estimated by the provided function.
+    /// Whenever an insertion pushes the estimate over the limit, elements are evicted in the
+    /// provided order until it fits again; evicted elements are reported to
+    /// [on_evict](crate::QueueBuilder::on_evict).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::mem::size_of;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, Eviction};
+    /// let queue = BlockingDelayQueue::builder()
+    ///     .memory_limit(
+    ///         64 * 1024 * 1024,
+    ///         |e: &DelayItem<String>| size_of::<DelayItem<String>>() + e.data.capacity(),
+    ///         Eviction::FurthestDeadline,
+    ///     )
+    ///     .build();
+    /// ```
+    pub fn memory_limit(
+        mut self,
+        max_bytes: usize,
+        estimate: impl Fn(&T) -> usize + Send + 'static,
+        eviction: Eviction,
+    ) -> Self {
+        self.memory = Some(MemoryLimit::new(max_bytes, estimate, eviction));
+        self
+    }
+
+    /// Sets a callback receiving elements evicted because the [memory limit](crate::QueueBuilder::memory_limit)
+    /// was exceeded. The callback runs on the producer thread whose insertion caused the eviction.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::builder()
+    ///     .on_evict(|e: DelayItem<&str>| println!("evicted {}", e.data))
+    ///     .build();
+    /// ```
+    pub fn on_evict(mut self, on_evict: impl Fn(T) + Send + Sync + 'static) -> Self {
+        self.on_evict = Some(Box::new(on_evict));
+        self
+    }
+
+    /// Sets the timeout used by [offer_default](crate::BlockingDelayQueue::offer_default), so
+    /// an application-wide policy doesn't have to be passed to every call site. Without it,
+    /// offer_default fails immediately on a full queue. Blocking [add](crate::BlockingDelayQueue::add) isn't affected.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .capacity(16)
+    ///     .default_offer_timeout(Duration::from_millis(100))
+    ///     .build();
+    /// ```
+    pub fn default_offer_timeout(mut self, timeout: Duration) -> Self {
+        self.default_offer_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout used by [poll_default](crate::BlockingDelayQueue::poll_default), so
+    /// an application-wide policy doesn't have to be passed to every call site. Without it,
+    /// poll_default returns [None](core::option::Option::None) immediately when no element is
+    /// expired. Blocking [take](crate::BlockingDelayQueue::take) isn't affected.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .default_poll_timeout(Duration::from_secs(1))
+    ///     .build();
+    /// ```
+    pub fn default_poll_timeout(mut self, timeout: Duration) -> Self {
+        self.default_poll_timeout = Some(timeout);
+        self
+    }
+
+    /// Caps every single wait of a blocked caller, so deadlines are re-checked at least once per
+    /// slice. On platforms where [Instant](std::time::Instant) doesn't advance during system
+    /// suspend, this bounds how late deliveries are after waking up.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .max_wait_slice(Duration::from_secs(1))
+    ///     .build();
+    /// ```
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// Sets a soft limit on the number of elements, below any hard capacity. The callback is invoked
+    /// with the queue size whenever an insertion makes the queue reach the limit, once per crossing,
+    /// and [is_under_pressure](crate::BlockingDelayQueue::is_under_pressure) reports it until the
+    /// queue shrinks below the limit again. The callback runs on the producer thread after the
+    /// queue lock was released.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .capacity(1000)
+    ///     .soft_limit(800, |size| println!("queue under pressure with {} elements", size))
+    ///     .build();
+    /// ```
+    pub fn soft_limit(
+        mut self,
+        limit: usize,
+        on_pressure: impl Fn(usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.soft_limit = Some((limit, Box::new(on_pressure)));
+        self
+    }
+
+    /// Sets an alert invoked with the lateness once expired elements wait longer than the provided
+    /// bound to be taken, e.g. because consumers stalled or can't keep up.
+    /// Lateness is checked whenever an element is added or taken, so producers detect stalled
+    /// consumers. The alert fires once per crossing and again only after the lateness dropped
+    /// back within the bound. It runs on the calling thread after the queue lock was released.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .lateness_alert(Duration::from_secs(5), |lateness| {
+    ///         eprintln!("consumers are {:?} behind", lateness)
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn lateness_alert(
+        mut self,
+        bound: Duration,
+        on_late: impl Fn(Duration) + Send + Sync + 'static,
+    ) -> Self {
+        self.lateness_alert = Some((bound, Box::new(on_late)));
+        self
+    }
+
+    /// Sets a callback invoked when the last pending element is taken, cleared, cancelled with
+    /// its scope or evicted while no producer is blocked, e.g. for shutting down the workers of a
+    /// batch job once all work is done.
+    /// It runs on the thread which emptied the queue after the queue lock was released.
+    /// See [wait_idle](crate::BlockingDelayQueue::wait_idle) for waiting instead.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .on_idle(|| println!("all work is done"))
+    ///     .build();
+    /// ```
+    pub fn on_idle(mut self, on_idle: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_idle = Some(Box::new(on_idle));
+        self
+    }
+
+    /// Wakes consumers waiting for the head's release time the provided margin early and
+    /// busy-waits the remainder, for deliveries with sub-millisecond precision at the cost of CPU
+    /// time. Only that wait spins; producers waiting for space and consumers waiting for their
+    /// timeout sleep as usual. Spinning stops once the head changes or the
+    /// [wait slice](QueueBuilder::max_wait_slice) is over. A suitable margin for the running host
+    /// can be measured with [calibrate](crate::calibrate).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{calibrate, BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .early_wake(calibrate().p99)
+    ///     .build();
+    /// ```
+    pub fn early_wake(mut self, margin: Duration) -> Self {
+        self.early_wake = Some(margin);
+        self
+    }
+
+    /// Moves the release time of every added element randomly by up to the provided window in
+    /// either direction, so elements scheduled for the same instant, e.g. thousands of cache
+    /// refreshes, are spread out instead of being released at once. The element itself is left
+    /// untouched; elements are released in order of their moved release times.
+    /// For jitter on single elements see [new_with_jitter](crate::DelayItem::new_with_jitter).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::builder()
+    ///     .jitter(Duration::from_millis(500))
+    ///     .build();
+    /// let refresh_at = Instant::now() + Duration::from_secs(60);
+    /// for key in 0..1000 {
+    ///     queue.add(DelayItem::new(key, refresh_at));
+    /// }
+    /// ```
+    pub fn jitter(mut self, window: Duration) -> Self {
+        self.jitter = Some(window);
+        self
+    }
+
+    /// Records every add, take and cancellation to the provided audit sink, rendering elements
+    /// with the redaction callback so sensitive fields never reach the audit trail.
+    /// The redaction callback of an added element runs while the queue is locked, so it should be cheap.
+    /// Requires the `audit` feature.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{AuditRecord, BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<(&str, &str)>>::builder()
+    ///     .audit(
+    ///         |record: AuditRecord| println!("{:?} {}", record.operation, record.payload),
+    ///         |e| format!("user={} card=****", e.data.0),
+    ///     )
+    ///     .build();
+    /// ```
+    #[cfg(feature = "audit")]
+    pub fn audit(
+        mut self,
+        sink: impl AuditSink + Send + Sync + 'static,
+        redact: impl Fn(&T) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.audit = Some(Auditor::new(sink, redact));
+        self
+    }
+
+    /// Drives the queue by the provided mock clock instead of the system clock, so tests control
+    /// when elements expire.
+    /// Advancing the clock doesn't wake blocked consumers. Like after a resume from suspend, they
+    /// notice it once they re-check, so combine it with a
+    /// [max_wait_slice](crate::QueueBuilder::max_wait_slice). [Early wakes](crate::QueueBuilder::early_wake)
+    /// are ignored.
+    /// Requires the `test-util` feature.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// use blocking_delay_queue::test_util::MockClock;
+    /// let clock = MockClock::new();
+    /// let queue = BlockingDelayQueue::builder().clock(&clock).build();
+    /// queue.add(DelayItem::new("refresh", clock.now() + Duration::from_secs(60)));
+    /// assert!(queue.poll(Duration::ZERO).is_none());
+    /// clock.advance(Duration::from_secs(60));
+    /// assert_eq!("refresh", queue.poll(Duration::ZERO).unwrap().data);
+    /// ```
+    #[cfg(feature = "test-util")]
+    pub fn clock(mut self, clock: &MockClock) -> Self {
+        self.clock = Some(clock.clone());
+        self
+    }
+
+    /// Applies the options of the provided configuration, replacing options set before, so
+    /// options read from a configuration file can be combined with callbacks.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, QueueConfig};
+    /// let cfg = QueueConfig::new().capacity(1000).soft_limit(800);
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder()
+    ///     .config(&cfg)
+    ///     .on_idle(|| println!("all work is done"))
+    ///     .build();
+    /// ```
+    pub fn config(mut self, cfg: &QueueConfig) -> Self {
+        self.name = cfg.name.clone();
+        self = self
+            .capacity(cfg.capacity)
+            .max_delivery_rate(cfg.max_delivery_rate);
+        self.tick_driven = cfg.tick_driven;
+        self.default_offer_timeout = cfg.default_offer_timeout;
+        self.default_poll_timeout = cfg.default_poll_timeout;
+        self.max_wait_slice = cfg.max_wait_slice;
+        self.early_wake = cfg.early_wake;
+        // a pressure callback set before is kept, only the limit is configured
+        self.soft_limit = match (cfg.soft_limit, self.soft_limit.take()) {
+            (Some(limit), Some((_, on_pressure))) => Some((limit, on_pressure)),
+            (Some(limit), None) => Some((limit, Box::new(|_| {}))),
+            (None, _) => None,
+        };
+        self
+    }
+
+    /// Creates the queue with the configured options.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<&str>>::builder().build();
+    /// ```
+    pub fn build(self) -> BlockingDelayQueue<T> {
+        BlockingDelayQueue::from_builder(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+
+    #[test]
+    fn should_build_bounded_queue() {
+        let queue = BlockingDelayQueue::builder().capacity(1).build();
+        assert!(queue.offer(DelayItem::new(1, Instant::now()), Duration::from_millis(5)));
+        assert!(!queue.offer(DelayItem::new(2, Instant::now()), Duration::from_millis(5)));
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_use_default_timeouts() {
+        let queue = BlockingDelayQueue::builder()
+            .capacity(1)
+            .default_offer_timeout(Duration::from_millis(5))
+            .default_poll_timeout(Duration::from_millis(5))
+            .build();
+        assert!(queue.offer_default(DelayItem::new(1, Instant::now() + Duration::from_secs(60))));
+        assert!(!queue.offer_default(DelayItem::new(2, Instant::now())));
+        assert!(queue.poll_default().is_none());
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_not_wait_without_default_timeouts() {
+        let queue = BlockingDelayQueue::builder().capacity(1).build();
+        assert!(queue.offer_default(DelayItem::new(1, Instant::now() + Duration::from_secs(60))));
+        assert!(!queue.offer_default(DelayItem::new(2, Instant::now())));
+        assert!(queue.poll_default().is_none());
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_pace_deliveries() {
+        let queue = BlockingDelayQueue::builder().max_delivery_rate(20).build();
+        for i in 0..3 {
+            queue.add(DelayItem::new(i, Instant::now()));
+        }
+
+        let now = Instant::now();
+        for i in 0..3 {
+            assert_eq!(i, queue.take().data);
+        }
+        // two intervals of 50ms between three deliveries
+        assert!(now.elapsed() >= Duration::from_millis(100));
+        assert!(queue.poll(Duration::ZERO).is_none());
+    }
+
+    #[cfg(feature = "audit")]
+    #[test]
+    fn should_record_redacted_audit_trail() {
+        use std::sync::{mpsc, Mutex};
+
+        use crate::audit::{AuditOperation, AuditRecord};
+
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let queue = BlockingDelayQueue::builder()
+            .name("billing")
+            .audit(
+                move |r: AuditRecord| tx.lock().unwrap().send(r).unwrap(),
+                |e: &DelayItem<(u32, &str)>| format!("account={}", e.data.0),
+            )
+            .build();
+        queue.add(DelayItem::new((1, "secret"), Instant::now()));
+        queue.add(DelayItem::new((2, "secret"), Instant::now()));
+        queue.take();
+        queue.clear();
+
+        let records: Vec<_> = rx.try_iter().collect();
+        assert!(records
+            .iter()
+            .all(|r| r.queue.as_deref() == Some("billing")));
+        let trail: Vec<_> = records
+            .into_iter()
+            .map(|r| (r.operation, r.payload))
+            .collect();
+        assert_eq!(
+            vec![
+                (AuditOperation::Add, "account=1".to_string()),
+                (AuditOperation::Add, "account=2".to_string()),
+                (AuditOperation::Take, "account=1".to_string()),
+                (AuditOperation::Cancel, "account=2".to_string()),
+            ],
+            trail
+        );
+    }
+}
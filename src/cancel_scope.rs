@@ -0,0 +1,52 @@
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+
+/// A group of elements added to a [BlockingDelayQueue](crate::BlockingDelayQueue) which can be
+/// cancelled all at once, e.g. all pending work of a connection or session, see
+/// [cancel_scope](crate::BlockingDelayQueue::cancel_scope).
+/// Cancelled elements are removed from the queue and their listeners, if any, are notified
+/// with [Cancelled](crate::Outcome::Cancelled).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// let queue = BlockingDelayQueue::new_unbounded();
+/// let connection = queue.cancel_scope();
+/// queue.add_scoped(&connection, DelayItem::new("keep-alive", Instant::now() + Duration::from_secs(30)));
+/// queue.add_scoped(&connection, DelayItem::new("idle-check", Instant::now() + Duration::from_secs(60)));
+/// assert_eq!(2, connection.cancel_all());
+/// assert_eq!(0, queue.size());
+/// ```
+pub struct CancelScope<'a, T> {
+    pub(crate) queue: &'a BlockingDelayQueue<T>,
+    pub(crate) id: u64,
+}
+
+impl<'a, T> CancelScope<'a, T>
+where
+    T: Delayed + Ord,
+{
+    pub(crate) fn new(queue: &'a BlockingDelayQueue<T>, id: u64) -> Self {
+        CancelScope { queue, id }
+    }
+
+    /// Removes all elements added within this scope which are still in the queue.
+    /// Returns the number of cancelled elements.
+    /// The scope stays usable, so elements added afterwards can be cancelled again.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::new_unbounded();
+    /// let session = queue.cancel_scope();
+    /// queue.add_scoped(&session, DelayItem::new(1, Instant::now()));
+    /// assert_eq!(1, session.cancel_all());
+    /// ```
+    pub fn cancel_all(&self) -> usize {
+        self.queue.cancel_scoped(self.id)
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+/// A configuration describing how a [BlockingDelayQueue](crate::BlockingDelayQueue) should be
+/// constructed, see [from_config](crate::BlockingDelayQueue::from_config). It mirrors the options
+/// of [QueueBuilder](crate::QueueBuilder) which don't take callbacks; apply it to a builder with
+/// [config](crate::QueueBuilder::config) to combine both.
+/// With the `serde` feature enabled it can be deserialized, so queue behavior can be tuned
+/// from a configuration file. Missing fields fall back to their defaults.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, QueueConfig};
+/// let cfg = QueueConfig::new()
+///     .name("orders")
+///     .capacity(16)
+///     .max_wait_slice(Duration::from_secs(1));
+/// let queue = BlockingDelayQueue::<DelayItem<&str>>::from_config(&cfg);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[non_exhaustive]
+pub struct QueueConfig {
+    /// The queue name, see [name](crate::QueueBuilder::name).
+    pub name: Option<String>,
+    /// Queue capacity where '0' is treated as unbounded.
+    pub capacity: usize,
+    /// Deliveries per second where '0' disables pacing, see
+    /// [max_delivery_rate](crate::QueueBuilder::max_delivery_rate).
+    pub max_delivery_rate: u32,
+    /// Whether elements are only released by ticks, see
+    /// [tick_driven](crate::QueueBuilder::tick_driven).
+    pub tick_driven: bool,
+    /// See [default_offer_timeout](crate::QueueBuilder::default_offer_timeout).
+    pub default_offer_timeout: Option<Duration>,
+    /// See [default_poll_timeout](crate::QueueBuilder::default_poll_timeout).
+    pub default_poll_timeout: Option<Duration>,
+    /// See [max_wait_slice](crate::QueueBuilder::max_wait_slice).
+    pub max_wait_slice: Option<Duration>,
+    /// See [early_wake](crate::QueueBuilder::early_wake).
+    pub early_wake: Option<Duration>,
+    /// A soft limit reported by [is_under_pressure](crate::BlockingDelayQueue::is_under_pressure),
+    /// see [soft_limit](crate::QueueBuilder::soft_limit) for a callback as well.
+    pub soft_limit: Option<usize>,
+}
+
+impl QueueConfig {
+    /// Creates a new configuration of an unbounded queue with all options at their defaults.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::QueueConfig;
+    /// let cfg = QueueConfig::new();
+    /// assert_eq!(0, cfg.capacity);
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the queue name, see [name](crate::QueueBuilder::name).
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the queue capacity where '0' is treated as unbounded.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the deliveries per second, see
+    /// [max_delivery_rate](crate::QueueBuilder::max_delivery_rate).
+    pub fn max_delivery_rate(mut self, per_second: u32) -> Self {
+        self.max_delivery_rate = per_second;
+        self
+    }
+
+    /// Releases elements only on ticks, see [tick_driven](crate::QueueBuilder::tick_driven).
+    pub fn tick_driven(mut self) -> Self {
+        self.tick_driven = true;
+        self
+    }
+
+    /// See [default_offer_timeout](crate::QueueBuilder::default_offer_timeout).
+    pub fn default_offer_timeout(mut self, timeout: Duration) -> Self {
+        self.default_offer_timeout = Some(timeout);
+        self
+    }
+
+    /// See [default_poll_timeout](crate::QueueBuilder::default_poll_timeout).
+    pub fn default_poll_timeout(mut self, timeout: Duration) -> Self {
+        self.default_poll_timeout = Some(timeout);
+        self
+    }
+
+    /// See [max_wait_slice](crate::QueueBuilder::max_wait_slice).
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// See [early_wake](crate::QueueBuilder::early_wake).
+    pub fn early_wake(mut self, margin: Duration) -> Self {
+        self.early_wake = Some(margin);
+        self
+    }
+
+    /// Sets a soft limit, see [soft_limit](crate::QueueBuilder::soft_limit).
+    pub fn soft_limit(mut self, limit: usize) -> Self {
+        self.soft_limit = Some(limit);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::config::QueueConfig;
+    use crate::delay_item::DelayItem;
+
+    #[test]
+    fn should_build_queue_from_config() {
+        let cfg = QueueConfig::new()
+            .name("orders")
+            .capacity(2)
+            .soft_limit(1)
+            .default_poll_timeout(Duration::from_millis(10));
+        let queue = BlockingDelayQueue::<DelayItem<i32>>::from_config(&cfg);
+
+        assert_eq!(Some("orders"), queue.name());
+        assert!(queue.poll_default().is_none());
+        queue.add(DelayItem::new(1, Instant::now()));
+        assert!(queue.is_under_pressure());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn should_deserialize_with_defaults() {
+        let cfg: QueueConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(QueueConfig::default(), cfg);
+
+        let cfg: QueueConfig = serde_json::from_str(r#"{"capacity": 8}"#).unwrap();
+        assert_eq!(8, cfg.capacity);
+    }
+}
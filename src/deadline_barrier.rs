@@ -0,0 +1,218 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::deadline_wait;
+
+struct Gate {
+    released: bool,
+    waiting: usize,
+}
+
+/// A barrier releasing all waiting threads together at a shared deadline, or earlier once
+/// ['release_now'] is called, e.g. for a coordinated start of load test workers.
+/// Threads arriving after the release pass through without waiting.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::DeadlineBarrier;
+/// let start = Instant::now() + Duration::from_millis(10);
+/// let barrier = Arc::new(DeadlineBarrier::new(start));
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let barrier = barrier.clone();
+///         thread::spawn(move || {
+///             barrier.wait();
+///             assert!(Instant::now() >= start);
+///         })
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+/// ```
+pub struct DeadlineBarrier {
+    gate: Mutex<Gate>,
+    condvar: Condvar,
+    deadline: Instant,
+    max_wait_slice: Option<Duration>,
+}
+
+impl DeadlineBarrier {
+    /// Creates a new barrier releasing its waiters at the provided deadline.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::DeadlineBarrier;
+    /// let barrier = DeadlineBarrier::new(Instant::now() + Duration::from_secs(1));
+    /// ```
+    pub fn new(deadline: Instant) -> Self {
+        DeadlineBarrier {
+            gate: Mutex::new(Gate {
+                released: false,
+                waiting: 0,
+            }),
+            condvar: Condvar::new(),
+            deadline,
+            max_wait_slice: None,
+        }
+    }
+
+    /// Caps every single wait of a waiting thread, like
+    /// [max_wait_slice](crate::QueueBuilder::max_wait_slice), so a deadline which passed while
+    /// the system was suspended releases the waiters within one slice after waking up.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::DeadlineBarrier;
+    /// let barrier = DeadlineBarrier::new(Instant::now() + Duration::from_secs(60))
+    ///     .max_wait_slice(Duration::from_secs(1));
+    /// ```
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// Blocks the current thread until the deadline expires or the barrier is released.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::DeadlineBarrier;
+    /// let barrier = DeadlineBarrier::new(Instant::now());
+    /// barrier.wait();
+    /// ```
+    pub fn wait(&self) {
+        let mut gate = self.gate_mutex();
+        gate.waiting += 1;
+        loop {
+            let now = Instant::now();
+            if gate.released || self.deadline <= now {
+                break;
+            }
+            gate = deadline_wait::wait_until(
+                &self.condvar,
+                gate,
+                Some(self.deadline),
+                now,
+                self.max_wait_slice,
+            )
+            .expect("Deadline barrier lock poisoned");
+        }
+        gate.waiting -= 1;
+    }
+
+    /// Releases all waiting threads before the deadline, and lets every thread arriving later pass.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::DeadlineBarrier;
+    /// let barrier = DeadlineBarrier::new(Instant::now() + Duration::from_secs(60));
+    /// barrier.release_now();
+    /// barrier.wait();
+    /// ```
+    pub fn release_now(&self) {
+        self.gate_mutex().released = true;
+        self.condvar.notify_all();
+    }
+
+    /// Returns the number of threads currently waiting on the barrier, e.g. to check that all
+    /// workers have arrived before the deadline.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::DeadlineBarrier;
+    /// let barrier = DeadlineBarrier::new(Instant::now() + Duration::from_secs(60));
+    /// assert_eq!(0, barrier.waiting());
+    /// ```
+    pub fn waiting(&self) -> usize {
+        self.gate_mutex().waiting
+    }
+
+    /// Returns the deadline at which waiting threads are released.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::DeadlineBarrier;
+    /// let deadline = Instant::now();
+    /// assert_eq!(deadline, DeadlineBarrier::new(deadline).deadline());
+    /// ```
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    fn gate_mutex(&self) -> MutexGuard<'_, Gate> {
+        self.gate.lock().expect("Deadline barrier lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::deadline_barrier::DeadlineBarrier;
+
+    #[test]
+    fn should_release_waiters_early() {
+        let barrier = Arc::new(DeadlineBarrier::new(
+            Instant::now() + Duration::from_secs(60),
+        ));
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let barrier = barrier.clone();
+                thread::spawn(move || barrier.wait())
+            })
+            .collect();
+        let start = Instant::now();
+        while barrier.waiting() < 3 {
+            assert!(start.elapsed() < Duration::from_secs(1));
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        barrier.release_now();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(0, barrier.waiting());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn should_release_waiters_at_deadline() {
+        let deadline = Instant::now() + Duration::from_millis(30);
+        let barrier =
+            Arc::new(DeadlineBarrier::new(deadline).max_wait_slice(Duration::from_millis(5)));
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    Instant::now()
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            let released = worker.join().unwrap();
+            assert!(released >= deadline);
+            assert!(released - deadline < Duration::from_secs(1));
+        }
+        assert_eq!(0, barrier.waiting());
+    }
+}
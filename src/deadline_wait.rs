@@ -0,0 +1,51 @@
+use std::sync::{Condvar, LockResult, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+// waits for a notification or until the deadline, but never longer than one wait slice, so the
+// deadline is re-checked regularly even if Instant didn't advance during a system suspend
+pub(crate) fn wait_until<'a, S>(
+    condvar: &Condvar,
+    guard: MutexGuard<'a, S>,
+    deadline: Option<Instant>,
+    now: Instant,
+    max_wait_slice: Option<Duration>,
+) -> LockResult<MutexGuard<'a, S>> {
+    let timeout = match (deadline.map(|d| d - now), max_wait_slice) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+    match timeout {
+        Some(timeout) => match condvar.wait_timeout(guard, timeout) {
+            Ok((guard, _)) => Ok(guard),
+            Err(e) => Err(PoisonError::new(e.into_inner().0)),
+        },
+        _ => condvar.wait(guard),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Condvar, Mutex};
+    use std::time::{Duration, Instant};
+
+    use crate::deadline_wait::wait_until;
+
+    #[test]
+    fn should_return_after_one_slice_when_clock_paused() {
+        let lock = Mutex::new(());
+        let condvar = Condvar::new();
+        let now = Instant::now();
+
+        // a clock paused during suspend leaves the deadline looking a minute away
+        let guard = wait_until(
+            &condvar,
+            lock.lock().unwrap(),
+            Some(now + Duration::from_secs(60)),
+            now,
+            Some(Duration::from_millis(10)),
+        );
+
+        assert!(guard.is_ok());
+        assert!(now.elapsed() < Duration::from_secs(1));
+    }
+}
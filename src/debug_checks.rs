@@ -0,0 +1,27 @@
+use std::cell::RefCell;
+
+thread_local! {
+    // the queues whose callbacks are currently running on this thread, innermost last
+    static DISPATCHING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+// pops the queue again even if the callback unwinds
+struct Dispatching;
+
+impl Drop for Dispatching {
+    fn drop(&mut self) {
+        DISPATCHING.with(|d| d.borrow_mut().pop());
+    }
+}
+
+// runs 'f' marked as a callback of the queue identified by 'queue'
+pub(crate) fn dispatch<R>(queue: usize, f: impl FnOnce() -> R) -> R {
+    DISPATCHING.with(|d| d.borrow_mut().push(queue));
+    let _dispatching = Dispatching;
+    f()
+}
+
+// whether the current thread is running a callback of the queue identified by 'queue'
+pub(crate) fn is_dispatching(queue: usize) -> bool {
+    DISPATCHING.with(|d| d.borrow().contains(&queue))
+}
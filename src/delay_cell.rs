@@ -0,0 +1,205 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::deadline_wait;
+
+enum Slot<T> {
+    Empty,
+    Set(T, Instant),
+    Cancelled,
+}
+
+/// A cell which is set once with a value and a deadline, making the value available to waiters
+/// once the deadline expires, i.e. a delay queue holding at most one element which is handed to
+/// every waiter.
+/// Cancelling the cell wakes all waiters without a value.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::DelayCell;
+/// let cell = Arc::new(DelayCell::new());
+/// let waiter = cell.clone();
+/// let handle = thread::spawn(move || waiter.wait());
+/// cell.set("ready", Instant::now() + Duration::from_millis(10)).unwrap();
+/// assert_eq!(Some("ready"), handle.join().unwrap());
+/// ```
+pub struct DelayCell<T> {
+    slot: Mutex<Slot<T>>,
+    condvar: Condvar,
+    max_wait_slice: Option<Duration>,
+}
+
+impl<T> Default for DelayCell<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> DelayCell<T>
+where
+    T: Clone,
+{
+    /// Creates a new empty cell.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::DelayCell;
+    /// let cell = DelayCell::<u64>::new();
+    /// ```
+    pub fn new() -> Self {
+        DelayCell {
+            slot: Mutex::new(Slot::Empty),
+            condvar: Condvar::new(),
+            max_wait_slice: None,
+        }
+    }
+
+    /// Caps every single wait of a waiter, like
+    /// [max_wait_slice](crate::QueueBuilder::max_wait_slice), so the deadline is re-checked at
+    /// least once per slice.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayCell;
+    /// let cell = DelayCell::<u64>::new().max_wait_slice(Duration::from_secs(1));
+    /// ```
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// Sets the value which becomes available once the deadline expires.
+    ///
+    /// # Errors
+    /// Hands the value back if the cell was already set or cancelled.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::DelayCell;
+    /// let cell = DelayCell::new();
+    /// assert!(cell.set(1, Instant::now()).is_ok());
+    /// assert_eq!(Err(2), cell.set(2, Instant::now()));
+    /// ```
+    pub fn set(&self, value: T, deadline: Instant) -> Result<(), T> {
+        let mut slot = self.slot_mutex();
+        match *slot {
+            Slot::Empty => {
+                *slot = Slot::Set(value, deadline);
+                self.condvar.notify_all();
+                Ok(())
+            }
+            _ => Err(value),
+        }
+    }
+
+    /// Waits until the cell is set and its deadline has expired, returning a copy of the value.
+    /// Returns [None](core::option::Option::None) if the cell is cancelled before.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::DelayCell;
+    /// let cell = DelayCell::new();
+    /// cell.set(123, Instant::now()).unwrap();
+    /// assert_eq!(Some(123), cell.wait());
+    /// ```
+    pub fn wait(&self) -> Option<T> {
+        let mut slot = self.slot_mutex();
+        loop {
+            let now = Instant::now();
+            let deadline = match &*slot {
+                Slot::Set(value, deadline) if *deadline <= now => return Some(value.clone()),
+                Slot::Set(_, deadline) => Some(*deadline),
+                Slot::Empty => None,
+                Slot::Cancelled => return None,
+            };
+            slot =
+                deadline_wait::wait_until(&self.condvar, slot, deadline, now, self.max_wait_slice)
+                    .expect("Delay cell lock poisoned");
+        }
+    }
+
+    /// Cancels the cell, waking all waiters without a value.
+    /// Returns 'false' if the value is already available or the cell was already cancelled,
+    /// 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::DelayCell;
+    /// let cell = DelayCell::new();
+    /// cell.set(123, Instant::now() + Duration::from_secs(60)).unwrap();
+    /// assert!(cell.cancel());
+    /// assert_eq!(None, cell.wait());
+    /// ```
+    pub fn cancel(&self) -> bool {
+        let mut slot = self.slot_mutex();
+        match *slot {
+            Slot::Set(_, deadline) if deadline <= Instant::now() => false,
+            Slot::Cancelled => false,
+            _ => {
+                *slot = Slot::Cancelled;
+                self.condvar.notify_all();
+                true
+            }
+        }
+    }
+
+    fn slot_mutex(&self) -> MutexGuard<'_, Slot<T>> {
+        self.slot.lock().expect("Delay cell lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::delay_cell::DelayCell;
+
+    #[test]
+    fn should_release_value_to_all_waiters_at_deadline() {
+        let cell = Arc::new(DelayCell::new());
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || cell.wait())
+            })
+            .collect();
+        let deadline = Instant::now() + Duration::from_millis(30);
+        cell.set(7, deadline).unwrap();
+
+        for waiter in waiters {
+            assert_eq!(Some(7), waiter.join().unwrap());
+        }
+        assert!(Instant::now() >= deadline);
+        assert!(!cell.cancel());
+    }
+
+    #[test]
+    fn should_abort_waiters_on_cancel() {
+        let cell = Arc::new(DelayCell::<u32>::new());
+        let waiter = cell.clone();
+        let handle = thread::spawn(move || waiter.wait());
+        thread::sleep(Duration::from_millis(10));
+
+        assert!(cell.cancel());
+        assert_eq!(None, handle.join().unwrap());
+        assert_eq!(Err(1), cell.set(1, Instant::now()));
+    }
+}
@@ -1,5 +1,15 @@
 use std::cmp::Ordering;
-use std::time::Instant;
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+// state of the jitter generator, see https://prng.di.unimi.it/splitmix64.c
+static JITTER_STATE: AtomicU64 = AtomicU64::new(0);
+static JITTER_SEED: OnceLock<u64> = OnceLock::new();
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
 
 /// A mix-in style trait for marking structures that can be used as expiring items.
 /// An implementation of this trait must define a [`delay`] method providing delay
@@ -52,7 +62,7 @@ impl<T> Ord for DelayItem<T> {
 
 impl<T> PartialOrd for DelayItem<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.delay.cmp(&other.delay))
+        Some(self.cmp(other))
     }
 }
 
@@ -68,6 +78,54 @@ impl<T> DelayItem<T> {
     pub fn new(data: T, delay: Instant) -> Self {
         DelayItem { data, delay }
     }
+
+    /// Creates a new item whose delay is moved randomly by up to the provided jitter in either
+    /// direction, so items scheduled for the same instant are spread out instead of all expiring
+    /// at once.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::DelayItem;
+    /// let refresh_at = Instant::now() + Duration::from_secs(60);
+    /// let jitter = Duration::from_millis(500);
+    /// let item = DelayItem::new_with_jitter("refresh", refresh_at, jitter);
+    /// assert!(item.delay >= refresh_at - jitter && item.delay <= refresh_at + jitter);
+    /// ```
+    pub fn new_with_jitter(data: T, delay: Instant, jitter: Duration) -> Self {
+        Self::new(data, jittered(delay, jitter))
+    }
+}
+
+// moves the delay randomly by up to the jitter in either direction, keeping it if moving it
+// would overflow
+pub(crate) fn jittered(delay: Instant, jitter: Duration) -> Instant {
+    let window = u64::try_from(jitter.as_nanos()).unwrap_or(u64::MAX);
+    if window == 0 {
+        return delay;
+    }
+    let random = next_jitter() % window.saturating_mul(2).saturating_add(1);
+    if random >= window {
+        let late = Duration::from_nanos(random - window);
+        delay.checked_add(late).unwrap_or(delay)
+    } else {
+        let early = Duration::from_nanos(window - random);
+        delay.checked_sub(early).unwrap_or(delay)
+    }
+}
+
+// a splitmix64 step over a shared counter, seeded once per process so separate processes spread
+// their deadlines differently
+fn next_jitter() -> u64 {
+    let seed = *JITTER_SEED.get_or_init(|| RandomState::new().build_hasher().finish());
+    let mut z = JITTER_STATE
+        .fetch_add(GOLDEN_GAMMA, AtomicOrdering::Relaxed)
+        .wrapping_add(seed)
+        .wrapping_add(GOLDEN_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
 }
 
 impl<T> Delayed for DelayItem<T> {
@@ -75,3 +133,32 @@ impl<T> Delayed for DelayItem<T> {
         self.delay
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::delay_item::DelayItem;
+
+    #[test]
+    fn should_spread_jittered_deadlines() {
+        let at = Instant::now() + Duration::from_secs(1);
+        let jitter = Duration::from_millis(100);
+        let delays = (0..16)
+            .map(|_| DelayItem::new_with_jitter((), at, jitter).delay)
+            .collect::<Vec<_>>();
+
+        assert!(delays
+            .iter()
+            .all(|d| *d >= at - jitter && *d <= at + jitter));
+        assert!(delays.iter().any(|d| *d != delays[0]));
+    }
+
+    #[test]
+    fn should_not_overflow_with_huge_jitter() {
+        let at = Instant::now();
+        for _ in 0..16 {
+            DelayItem::new_with_jitter((), at, Duration::MAX);
+        }
+    }
+}
@@ -0,0 +1,849 @@
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+use crate::thread_config::ThreadConfig;
+use crate::trace_context::{ContextCapture, TraceContext};
+use crate::watchdog::Watchdog;
+
+const PENDING: u8 = 0;
+const RUNNING: u8 = 1;
+const DONE: u8 = 2;
+const CANCELLED: u8 = 3;
+
+// the number of panics kept for 'recent_panics'
+const RECENT_PANICS: usize = 16;
+
+thread_local! {
+    // whether panics on this thread happen in an executor callback
+    static IN_CALLBACK: Cell<bool> = const { Cell::new(false) };
+    // the backtrace of the last panic of a callback on this thread, if backtraces are recorded
+    static BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+type Callback = Box<dyn FnOnce() + Send>;
+
+enum Completion<R> {
+    Pending,
+    Ready(Result<R, Panicked>),
+    // cancelled, discarded or already handed out
+    Abandoned,
+}
+
+struct Promise<R> {
+    completion: Mutex<Completion<R>>,
+    condvar: Condvar,
+}
+
+// completes the promise as abandoned if the computation is dropped without running
+struct Fulfiller<R>(Arc<Promise<R>>);
+
+struct Panics {
+    count: AtomicUsize,
+    recent: Mutex<VecDeque<Panicked>>,
+}
+
+enum Task {
+    Run(Callback, Arc<AtomicU8>),
+    Stop,
+}
+
+/// An executor running one-shot callbacks on a dedicated thread once their delay has expired,
+/// for scheduling work without managing a consumer thread.
+/// Callbacks run without holding any lock, so they can schedule further timers on the same
+/// executor, including timers due before ones already scheduled. Scheduling never blocks since
+/// timers are queued without bound, but callbacks run one at a time, so a callback must not block
+/// on adding to a full bounded queue which only this executor's timers drain.
+/// Timers which aren't due yet when the executor is dropped are discarded.
+/// A panicking callback is caught and recorded, see ['panic_count'] and ['recent_panics'], and
+/// doesn't stop the executor.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::mpsc;
+/// use std::time::Duration;
+/// use blocking_delay_queue::DelayedExecutor;
+/// let (tx, rx) = mpsc::channel();
+/// let executor = DelayedExecutor::new();
+/// executor.spawn_timer(Duration::from_millis(10), move || tx.send("fired").unwrap());
+/// assert_eq!("fired", rx.recv().unwrap());
+/// ```
+pub struct DelayedExecutor {
+    queue: Arc<BlockingDelayQueue<DelayItem<Task>>>,
+    panics: Arc<Panics>,
+    handle: Option<JoinHandle<()>>,
+    // captures the context of every scheduled callback, if created with a trace context
+    capture: Option<ContextCapture>,
+}
+
+/// The error returned for a computation which panicked, see
+/// [CompletionHandle::wait](crate::CompletionHandle::wait), carrying the panic message and the
+/// backtrace if backtraces are recorded, see
+/// [record_panic_backtraces](crate::DelayedExecutor::record_panic_backtraces). Panics of every callback are recorded the same way, see
+/// [recent_panics](crate::DelayedExecutor::recent_panics).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panicked {
+    message: String,
+    backtrace: Option<String>,
+}
+
+/// A handle to a callback scheduled by [DelayedExecutor](crate::DelayedExecutor::spawn_timer),
+/// used to cancel it or to check whether it has run.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::DelayedExecutor;
+/// let executor = DelayedExecutor::new();
+/// let timer = executor.spawn_timer(Duration::from_secs(5), || println!("too late"));
+/// assert!(timer.cancel());
+/// assert!(!timer.is_done());
+/// ```
+pub struct TimerHandle {
+    state: Arc<AtomicU8>,
+}
+
+/// A handle to a computation scheduled by
+/// [DelayedExecutor](crate::DelayedExecutor::spawn_computation), used to wait for its result or
+/// to cancel it.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::DelayedExecutor;
+/// let executor = DelayedExecutor::new();
+/// let answer = executor.spawn_computation(Duration::from_millis(10), || 6 * 7);
+/// assert_eq!(Some(Ok(42)), answer.wait());
+/// ```
+pub struct CompletionHandle<R> {
+    timer: TimerHandle,
+    promise: Arc<Promise<R>>,
+}
+
+impl Default for DelayedExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DelayedExecutor {
+    /// Creates a new executor with its own thread running the callbacks.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::new_with_thread_config(&ThreadConfig::new())
+    }
+
+    /// Creates a new executor whose thread is spawned as described by the provided configuration.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayedExecutor, ThreadConfig};
+    /// let executor = DelayedExecutor::new_with_thread_config(&ThreadConfig::new().name_prefix("billing"));
+    /// ```
+    pub fn new_with_thread_config(thread_config: &ThreadConfig) -> Self {
+        Self::start(thread_config, None, None)
+    }
+
+    /// Creates a new executor capturing the current [TraceContext](crate::TraceContext) whenever
+    /// a callback or computation is scheduled and running it within that context, so traces
+    /// continue on the executor's thread without scheduling every timer through
+    /// ['spawn_timer_traced'].
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{DelayedExecutor, ThreadConfig, TraceContext};
+    /// struct Span;
+    /// impl TraceContext for Span {
+    ///     fn current() -> Self {
+    ///         Span
+    ///     }
+    ///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+    ///         f()
+    ///     }
+    /// }
+    /// let executor = DelayedExecutor::new_with_trace_context::<Span>(&ThreadConfig::new());
+    /// let answer = executor.spawn_computation(Duration::ZERO, || 42);
+    /// assert_eq!(Some(Ok(42)), answer.wait());
+    /// ```
+    pub fn new_with_trace_context<C: TraceContext>(thread_config: &ThreadConfig) -> Self {
+        Self::start(thread_config, None, Some(ContextCapture::new::<C>()))
+    }
+
+    /// Creates a new executor which detects stalled callbacks, i.e. callbacks still running after
+    /// the provided maximum runtime, so one hung callback doesn't silently delay all later timers.
+    /// The stall callback is invoked with the time the stalled callback started, once per stalled
+    /// callback, on a watchdog thread spawned as described by the provided configuration.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::mpsc;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{DelayedExecutor, ThreadConfig};
+    /// let (tx, rx) = mpsc::channel();
+    /// let executor = DelayedExecutor::new_with_stall_detection(
+    ///     &ThreadConfig::new(),
+    ///     Duration::from_millis(10),
+    ///     move |started| tx.send(started.elapsed()).unwrap(),
+    /// );
+    /// executor.spawn_timer(Duration::ZERO, || thread::sleep(Duration::from_millis(50)));
+    /// assert!(rx.recv().unwrap() >= Duration::from_millis(10));
+    /// ```
+    pub fn new_with_stall_detection(
+        thread_config: &ThreadConfig,
+        max_runtime: Duration,
+        on_stall: impl Fn(Instant) + Send + 'static,
+    ) -> Self {
+        let watchdog = Watchdog::new_with_thread_config(on_stall, thread_config);
+        Self::start(thread_config, Some((watchdog, max_runtime)), None)
+    }
+
+    fn start(
+        thread_config: &ThreadConfig,
+        stalls: Option<(Watchdog<Instant>, Duration)>,
+        capture: Option<ContextCapture>,
+    ) -> Self {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let panics = Arc::new(Panics {
+            count: AtomicUsize::new(0),
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_PANICS)),
+        });
+        let (thread_queue, thread_panics) = (queue.clone(), panics.clone());
+        let handle = thread_config.spawn("delayed-executor", move || {
+            Self::run(&thread_queue, &thread_panics, stalls.as_ref())
+        });
+
+        DelayedExecutor {
+            queue,
+            panics,
+            handle: Some(handle),
+            capture,
+        }
+    }
+
+    /// Schedules the provided callback to run after the delay unless it's cancelled through the
+    /// returned handle in the meantime.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let timer = executor.spawn_timer(Duration::from_secs(5), || println!("5 seconds later"));
+    /// ```
+    pub fn spawn_timer(&self, delay: Duration, f: impl FnOnce() + Send + 'static) -> TimerHandle {
+        let state = Arc::new(AtomicU8::new(PENDING));
+        let f: Callback = match &self.capture {
+            Some(capture) => {
+                let context = capture.capture();
+                Box::new(move || context.in_scope(f))
+            }
+            None => Box::new(f),
+        };
+        self.queue.add(DelayItem::new(
+            Task::Run(f, state.clone()),
+            Instant::now() + delay,
+        ));
+        TimerHandle { state }
+    }
+
+    /// Schedules the provided callback like ['spawn_timer'], capturing the current
+    /// [TraceContext](crate::TraceContext) and running the callback within it, so the trace
+    /// continues on the executor's thread.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{DelayedExecutor, TraceContext};
+    /// struct Span;
+    /// impl TraceContext for Span {
+    ///     fn current() -> Self {
+    ///         Span
+    ///     }
+    ///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+    ///         f()
+    ///     }
+    /// }
+    /// let executor = DelayedExecutor::new();
+    /// executor.spawn_timer_traced::<Span>(Duration::from_secs(5), || println!("still traced"));
+    /// ```
+    pub fn spawn_timer_traced<C: TraceContext>(
+        &self,
+        delay: Duration,
+        f: impl FnOnce() + Send + 'static,
+    ) -> TimerHandle {
+        let context = C::current();
+        self.spawn_timer(delay, move || context.in_scope(f))
+    }
+
+    /// Schedules the provided computation to run after the delay, returning a handle to wait for
+    /// its result, or for the [Panicked](crate::Panicked) error if the computation panics.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let sum = executor.spawn_computation(Duration::from_millis(10), || (1..=10).sum::<u32>());
+    /// assert_eq!(Some(Ok(55)), sum.wait());
+    /// ```
+    pub fn spawn_computation<R>(
+        &self,
+        delay: Duration,
+        f: impl FnOnce() -> R + Send + 'static,
+    ) -> CompletionHandle<R>
+    where
+        R: Send + 'static,
+    {
+        let promise = Arc::new(Promise {
+            completion: Mutex::new(Completion::Pending),
+            condvar: Condvar::new(),
+        });
+        let fulfiller = Fulfiller(promise.clone());
+        let timer = self.spawn_timer(delay, move || {
+            match panic::catch_unwind(AssertUnwindSafe(f)) {
+                Ok(r) => fulfiller.0.complete(Completion::Ready(Ok(r))),
+                Err(payload) => {
+                    fulfiller
+                        .0
+                        .complete(Completion::Ready(Err(Panicked::new(&*payload))));
+                    // the executor counts the panic like any other
+                    panic::resume_unwind(payload)
+                }
+            }
+        });
+        CompletionHandle { timer, promise }
+    }
+
+    /// Returns the number of scheduled callbacks, including cancelled ones until their delay expires.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// assert_eq!(0, executor.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+
+    /// Returns the number of callbacks which panicked since the executor was created.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// assert_eq!(0, executor.panic_count());
+    /// ```
+    pub fn panic_count(&self) -> usize {
+        self.panics.count.load(Ordering::Acquire)
+    }
+
+    /// Returns the most recent panics of callbacks, oldest first, including those of timers
+    /// scheduled by ['spawn_timer']. Only the last few panics are kept.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let failed = executor.spawn_computation(Duration::ZERO, || -> u32 { panic!("boom") });
+    /// failed.wait();
+    /// // callbacks run one at a time, so the panic is recorded once a later computation completed
+    /// executor.spawn_computation(Duration::ZERO, || ()).wait();
+    /// assert_eq!("boom", executor.recent_panics()[0].message());
+    /// ```
+    pub fn recent_panics(&self) -> Vec<Panicked> {
+        self.panics.recent_mutex().iter().cloned().collect()
+    }
+
+    /// Installs a process-wide panic hook, chained to the current one, recording the backtrace of
+    /// panicking callbacks of all executors, see [backtrace](crate::Panicked::backtrace).
+    /// By the time a panic is caught, the stack it happened on is gone, so backtraces can only be
+    /// recorded by a hook. Executors never install it themselves; call this once at startup, after
+    /// any other hook was installed. Further calls have no effect.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// DelayedExecutor::record_panic_backtraces();
+    /// ```
+    pub fn record_panic_backtraces() {
+        static INSTALLED: Once = Once::new();
+        INSTALLED.call_once(|| {
+            let previous = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                if IN_CALLBACK.with(Cell::get) {
+                    let backtrace = Backtrace::capture();
+                    if backtrace.status() == BacktraceStatus::Captured {
+                        BACKTRACE.with(|b| *b.borrow_mut() = Some(backtrace.to_string()));
+                    }
+                }
+                previous(info)
+            }));
+        });
+    }
+
+    fn run(
+        queue: &BlockingDelayQueue<DelayItem<Task>>,
+        panics: &Panics,
+        stalls: Option<&(Watchdog<Instant>, Duration)>,
+    ) {
+        while let Task::Run(f, state) = queue.take().data {
+            // cancellation and execution race on the same transition, so only one of them wins
+            if state
+                .compare_exchange(PENDING, RUNNING, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // callbacks run one at a time, so the start time identifies the running one
+                let started = Instant::now();
+                if let Some((watchdog, max_runtime)) = stalls {
+                    watchdog.arm(started, *max_runtime);
+                }
+                // a panicking callback must not take the other timers down with the thread
+                IN_CALLBACK.with(|c| c.set(true));
+                let result = panic::catch_unwind(AssertUnwindSafe(f));
+                IN_CALLBACK.with(|c| c.set(false));
+                if let Err(payload) = result {
+                    panics.record(Panicked::new(&*payload));
+                }
+                BACKTRACE.with(|b| b.borrow_mut().take());
+                if let Some((watchdog, _)) = stalls {
+                    watchdog.disarm(&started);
+                }
+                state.store(DONE, Ordering::Release);
+            }
+        }
+    }
+}
+
+impl Drop for DelayedExecutor {
+    fn drop(&mut self) {
+        self.queue.add(DelayItem::new(Task::Stop, Instant::now()));
+        if let Some(handle) = self.handle.take() {
+            // the last reference may be dropped by one of its own callbacks, which can't wait for itself
+            if handle.thread().id() != thread::current().id() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+impl TimerHandle {
+    /// Cancels the callback if it hasn't started running yet.
+    /// Returns 'true' if the callback won't run because of this call, 'false' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let timer = executor.spawn_timer(Duration::from_secs(5), || println!("too late"));
+    /// assert!(timer.cancel());
+    /// assert!(!timer.cancel());
+    /// ```
+    pub fn cancel(&self) -> bool {
+        self.state
+            .compare_exchange(PENDING, CANCELLED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// Returns 'true' once the callback has finished running, 'false' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let timer = executor.spawn_timer(Duration::from_secs(5), || println!("5 seconds later"));
+    /// assert!(!timer.is_done());
+    /// ```
+    pub fn is_done(&self) -> bool {
+        self.state.load(Ordering::Acquire) == DONE
+    }
+}
+
+impl<R> CompletionHandle<R> {
+    /// Waits until the computation has run and returns its result, or the
+    /// [Panicked](crate::Panicked) error if it panicked.
+    /// Returns [None](core::option::Option::None) if the computation was cancelled or discarded
+    /// because the executor was dropped before it was due.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let answer = executor.spawn_computation(Duration::ZERO, || 42);
+    /// assert_eq!(Some(Ok(42)), answer.wait());
+    /// ```
+    pub fn wait(self) -> Option<Result<R, Panicked>> {
+        let mut completion = self.promise.completion_mutex();
+        while let Completion::Pending = *completion {
+            completion = self
+                .promise
+                .condvar
+                .wait(completion)
+                .expect("Completion lock poisoned");
+        }
+        Promise::take(&mut completion)
+    }
+
+    /// Waits until the computation has run or the specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no result is available within the specified
+    /// wait time, or the computation won't run at all.
+    /// The result is handed out once, later calls return [None](core::option::Option::None).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let answer = executor.spawn_computation(Duration::from_secs(60), || 42);
+    /// assert_eq!(None, answer.wait_timeout(Duration::from_millis(1)));
+    /// ```
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<R, Panicked>> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut completion = self.promise.completion_mutex();
+        while let Completion::Pending = *completion {
+            let condvar = &self.promise.condvar;
+            completion = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline <= now {
+                        return None;
+                    }
+                    condvar
+                        .wait_timeout(completion, deadline - now)
+                        .expect("Completion lock poisoned")
+                        .0
+                }
+                // a timeout too long to represent waits like 'wait'
+                _ => condvar.wait(completion).expect("Completion lock poisoned"),
+            };
+        }
+        Promise::take(&mut completion)
+    }
+
+    /// Cancels the computation if it hasn't started running yet, see
+    /// [TimerHandle::cancel](crate::TimerHandle::cancel).
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let answer = executor.spawn_computation(Duration::from_secs(60), || 42);
+    /// assert!(answer.cancel());
+    /// assert_eq!(None, answer.wait());
+    /// ```
+    pub fn cancel(&self) -> bool {
+        let cancelled = self.timer.cancel();
+        if cancelled {
+            self.promise.complete(Completion::Abandoned);
+        }
+        cancelled
+    }
+}
+
+impl Panics {
+    fn recent_mutex(&self) -> MutexGuard<'_, VecDeque<Panicked>> {
+        self.recent.lock().expect("Panics lock poisoned")
+    }
+
+    fn record(&self, panicked: Panicked) {
+        let mut recent = self.recent_mutex();
+        if recent.len() == RECENT_PANICS {
+            recent.pop_front();
+        }
+        recent.push_back(panicked);
+        self.count.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl Panicked {
+    fn new(payload: &(dyn Any + Send)) -> Self {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            _ => match payload.downcast_ref::<String>() {
+                Some(message) => message.clone(),
+                _ => "Box<dyn Any>".to_string(),
+            },
+        };
+        Panicked {
+            message,
+            backtrace: BACKTRACE.with(|b| b.borrow().clone()),
+        }
+    }
+
+    /// Returns the panic message, or `Box<dyn Any>` if the panic payload isn't a string.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// let executor = DelayedExecutor::new();
+    /// let failed = executor.spawn_computation(Duration::ZERO, || -> u32 { panic!("boom") });
+    /// assert_eq!("boom", failed.wait().unwrap().unwrap_err().message());
+    /// ```
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the backtrace of the panic if it was recorded, which requires the panic hook
+    /// installed by [record_panic_backtraces](crate::DelayedExecutor::record_panic_backtraces)
+    /// and backtraces to be enabled, e.g. through the 'RUST_BACKTRACE' or 'RUST_LIB_BACKTRACE'
+    /// environment variable.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayedExecutor;
+    /// DelayedExecutor::record_panic_backtraces();
+    /// let executor = DelayedExecutor::new();
+    /// let failed = executor.spawn_computation(Duration::ZERO, || -> u32 { panic!("boom") });
+    /// if let Some(backtrace) = failed.wait().unwrap().unwrap_err().backtrace() {
+    ///     eprintln!("{}", backtrace);
+    /// }
+    /// ```
+    pub fn backtrace(&self) -> Option<&str> {
+        self.backtrace.as_deref()
+    }
+}
+
+impl fmt::Display for Panicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "computation panicked: {}", self.message)
+    }
+}
+
+impl Error for Panicked {}
+
+impl<R> Promise<R> {
+    fn completion_mutex(&self) -> MutexGuard<'_, Completion<R>> {
+        self.completion.lock().expect("Completion lock poisoned")
+    }
+
+    // only the first completion counts
+    fn complete(&self, result: Completion<R>) {
+        let mut completion = self.completion_mutex();
+        if let Completion::Pending = *completion {
+            *completion = result;
+            self.condvar.notify_all();
+        }
+    }
+
+    fn take(completion: &mut Completion<R>) -> Option<Result<R, Panicked>> {
+        match mem::replace(completion, Completion::Abandoned) {
+            Completion::Ready(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+impl<R> Drop for Fulfiller<R> {
+    fn drop(&mut self) {
+        self.0.complete(Completion::Abandoned);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::delayed_executor::DelayedExecutor;
+    use crate::thread_config::ThreadConfig;
+    use crate::trace_context::TraceContext;
+
+    #[test]
+    fn should_run_timers_in_delay_order() {
+        let (tx, rx) = mpsc::channel();
+        let executor = DelayedExecutor::new();
+        let tx2 = tx.clone();
+        let second = executor.spawn_timer(Duration::from_millis(20), move || tx2.send(2).unwrap());
+        let first = executor.spawn_timer(Duration::from_millis(5), move || tx.send(1).unwrap());
+
+        assert_eq!(1, rx.recv_timeout(Duration::from_secs(1)).unwrap());
+        assert_eq!(2, rx.recv_timeout(Duration::from_secs(1)).unwrap());
+        assert!(!second.cancel());
+        drop(executor);
+        assert!(first.is_done() && second.is_done());
+    }
+
+    #[test]
+    fn should_not_run_cancelled_timer() {
+        let (tx, rx) = mpsc::channel();
+        let executor = DelayedExecutor::new();
+        let timer = executor.spawn_timer(Duration::from_millis(10), move || tx.send(()).unwrap());
+
+        assert!(timer.cancel());
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        assert!(!timer.is_done());
+        assert_eq!(0, executor.size());
+    }
+
+    #[test]
+    fn should_schedule_from_own_callbacks() {
+        let (tx, rx) = mpsc::channel();
+        let executor = Arc::new(DelayedExecutor::new());
+        let chain = executor.clone();
+        // a far timer is scheduled first, every link re-adds ahead of it
+        executor.spawn_timer(Duration::from_secs(60), || {});
+        executor.spawn_timer(Duration::ZERO, move || schedule_link(chain, 0, tx));
+
+        assert_eq!(100, rx.recv_timeout(Duration::from_secs(5)).unwrap());
+        assert_eq!(1, executor.size());
+    }
+
+    #[test]
+    fn should_deliver_computation_results() {
+        let executor = DelayedExecutor::new();
+        let slow = executor.spawn_computation(Duration::from_millis(30), || "slow");
+        let fast = executor.spawn_computation(Duration::from_millis(5), || "fast");
+
+        assert_eq!(None, slow.wait_timeout(Duration::from_millis(1)));
+        assert_eq!(Some(Ok("fast")), fast.wait());
+        assert_eq!(Some(Ok("slow")), slow.wait_timeout(Duration::from_secs(1)));
+        let unbounded = executor.spawn_computation(Duration::ZERO, || "unbounded");
+        assert_eq!(Some(Ok("unbounded")), unbounded.wait_timeout(Duration::MAX));
+
+        let discarded = executor.spawn_computation(Duration::from_secs(60), || "never");
+        drop(executor);
+        assert_eq!(None, discarded.wait());
+    }
+
+    #[test]
+    fn should_survive_panicking_callbacks() {
+        let executor = DelayedExecutor::new();
+        let failed = executor.spawn_computation(Duration::ZERO, || -> u32 { panic!("boom") });
+        let timer = executor.spawn_timer(Duration::from_millis(1), || panic!("{}", 42));
+        let later = executor.spawn_computation(Duration::from_millis(5), || 7);
+
+        assert_eq!("boom", failed.wait().unwrap().unwrap_err().message());
+        assert_eq!(Some(Ok(7)), later.wait());
+        assert!(timer.is_done());
+        assert_eq!(2, executor.panic_count());
+        let recent: Vec<_> = executor
+            .recent_panics()
+            .iter()
+            .map(|p| p.message().to_string())
+            .collect();
+        assert_eq!(vec!["boom", "42"], recent);
+    }
+
+    #[test]
+    fn should_report_stalled_callbacks() {
+        let (tx, rx) = mpsc::channel();
+        let executor = DelayedExecutor::new_with_stall_detection(
+            &ThreadConfig::new(),
+            Duration::from_millis(30),
+            move |started| tx.send(started).unwrap(),
+        );
+        let fast = executor.spawn_computation(Duration::ZERO, Instant::now);
+        let hung = executor.spawn_computation(Duration::from_millis(5), || {
+            thread::sleep(Duration::from_millis(100));
+            Instant::now()
+        });
+
+        let started = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(fast.wait().unwrap().unwrap() < started);
+        assert!(hung.wait().unwrap().unwrap() - started >= Duration::from_millis(100));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn should_run_traced_timers_within_captured_context() {
+        let executor = DelayedExecutor::new();
+        let (tx, rx) = mpsc::channel();
+        REQUEST.with(|r| r.set(7));
+        executor.spawn_timer_traced::<RequestId>(Duration::ZERO, move || {
+            tx.send(REQUEST.with(Cell::get)).unwrap()
+        });
+        REQUEST.with(|r| r.set(0));
+
+        assert_eq!(7, rx.recv().unwrap());
+    }
+
+    #[test]
+    fn should_run_every_callback_within_context_captured_at_scheduling() {
+        let executor = DelayedExecutor::new_with_trace_context::<RequestId>(&ThreadConfig::new());
+        REQUEST.with(|r| r.set(3));
+        let late =
+            executor.spawn_computation(Duration::from_millis(20), || REQUEST.with(Cell::get));
+        REQUEST.with(|r| r.set(4));
+        let early = executor.spawn_computation(Duration::ZERO, || REQUEST.with(Cell::get));
+        REQUEST.with(|r| r.set(0));
+
+        assert_eq!(Some(Ok(4)), early.wait());
+        assert_eq!(Some(Ok(3)), late.wait());
+    }
+
+    thread_local!(static REQUEST: Cell<u64> = const { Cell::new(0) });
+
+    struct RequestId(u64);
+
+    impl TraceContext for RequestId {
+        fn current() -> Self {
+            RequestId(REQUEST.with(Cell::get))
+        }
+
+        fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+            let outer = REQUEST.with(|r| r.replace(self.0));
+            let r = f();
+            REQUEST.with(|r| r.set(outer));
+            r
+        }
+    }
+
+    fn schedule_link(executor: Arc<DelayedExecutor>, n: u32, tx: mpsc::Sender<u32>) {
+        if n == 100 {
+            tx.send(n).unwrap();
+            return;
+        }
+        let next = executor.clone();
+        executor.spawn_timer(Duration::from_micros(100), move || {
+            schedule_link(next, n + 1, tx)
+        });
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+/// The number of recent operations kept per kind for [Diagnostics](crate::Diagnostics).
+pub(crate) const RECENT_OPERATIONS: usize = 8;
+
+// a ring of the times of the most recent operations of one kind, fixed in size so recording an
+// operation never allocates
+pub(crate) struct RecentOperations {
+    times: [Option<Instant>; RECENT_OPERATIONS],
+    next: usize,
+}
+
+impl RecentOperations {
+    pub(crate) const fn new() -> Self {
+        RecentOperations {
+            times: [None; RECENT_OPERATIONS],
+            next: 0,
+        }
+    }
+
+    pub(crate) fn record(&mut self, at: Instant) {
+        self.times[self.next] = Some(at);
+        self.next = (self.next + 1) % RECENT_OPERATIONS;
+    }
+
+    // oldest first
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Instant> + '_ {
+        (0..RECENT_OPERATIONS).filter_map(move |i| self.times[(self.next + i) % RECENT_OPERATIONS])
+    }
+}
+
+/// A point-in-time view of a queue for bug reports and health endpoints, see
+/// [diagnostics](crate::BlockingDelayQueue::diagnostics).
+/// All values are captured under the queue lock, so they're consistent with each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// The number of elements in the queue.
+    pub pending: usize,
+    /// The number of elements whose delay expired but which weren't taken yet.
+    pub expired: usize,
+    /// How long ago the delay of the longest waiting expired element expired.
+    pub oldest_expired_age: Option<Duration>,
+    /// The number of producers blocked until space becomes available.
+    pub blocked_producers: usize,
+    /// The number of consumers blocked until an element can be taken.
+    pub blocked_consumers: usize,
+    /// The times of the last (up to eight) added elements, oldest first.
+    pub last_adds: Vec<Instant>,
+    /// The times of the last (up to eight) taken elements, oldest first.
+    pub last_takes: Vec<Instant>,
+}
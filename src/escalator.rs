@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+
+struct Open<T> {
+    generation: u64,
+    item: T,
+    started: Instant,
+}
+
+struct Escalations<K, T> {
+    entries: HashMap<K, Open<T>>,
+    generation: u64,
+}
+
+/// A delivery of an [Escalator](crate::Escalator), where 'level' is the index of the reached
+/// escalation step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation<K, T> {
+    /// The key of the escalated item.
+    pub key: K,
+    /// A copy of the escalated item.
+    pub item: T,
+    /// The index of the reached escalation step, starting at '0'.
+    pub level: usize,
+}
+
+/// A scheduler delivering one logical item repeatedly at increasing escalation levels, e.g.
+/// remind, warn and escalate, until it's resolved.
+/// Every level is due at its offset from the time the item was escalated. Resolving an item
+/// cancels all of its remaining levels. Deliveries are handed to the consumer calling
+/// ['take'] / ['poll'].
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::Escalator;
+/// let escalator = Escalator::new(&[Duration::from_millis(5), Duration::from_millis(10)]);
+/// escalator.escalate("incident-1", "disk full");
+/// assert_eq!(0, escalator.take().level);
+/// assert!(escalator.resolve(&"incident-1"));
+/// assert!(escalator.poll(Duration::from_millis(20)).is_none());
+/// ```
+pub struct Escalator<K, T> {
+    queue: BlockingDelayQueue<DelayItem<(K, u64, usize)>>,
+    escalations: Mutex<Escalations<K, T>>,
+    levels: Vec<Duration>,
+}
+
+impl<K, T> Escalator<K, T>
+where
+    K: Eq + Hash + Clone,
+    T: Clone,
+{
+    /// Creates a new escalator with the provided escalation levels, given as offsets from the
+    /// time an item is escalated.
+    ///
+    /// # Panics
+    /// Panics if no level is provided or the offsets aren't increasing.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Escalator;
+    /// let day = Duration::from_secs(86_400);
+    /// let escalator = Escalator::<u64, String>::new(&[day, 3 * day, 7 * day]);
+    /// ```
+    pub fn new(levels: &[Duration]) -> Self {
+        assert!(!levels.is_empty(), "Escalator requires at least one level");
+        assert!(
+            levels.windows(2).all(|w| w[0] < w[1]),
+            "Escalation levels must be increasing"
+        );
+        Escalator {
+            queue: BlockingDelayQueue::new_unbounded(),
+            escalations: Mutex::new(Escalations {
+                entries: HashMap::new(),
+                generation: 0,
+            }),
+            levels: levels.to_vec(),
+        }
+    }
+
+    /// Starts escalating an item, replacing the item with the same key and restarting its levels.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Escalator;
+    /// let escalator = Escalator::new(&[Duration::from_secs(60)]);
+    /// escalator.escalate(1, "unpaid invoice");
+    /// ```
+    pub fn escalate(&self, key: K, item: T) {
+        let now = Instant::now();
+        let mut escalations = self.escalations_mutex();
+        escalations.generation += 1;
+        let generation = escalations.generation;
+        let open = Open {
+            generation,
+            item,
+            started: now,
+        };
+        escalations.entries.insert(key.clone(), open);
+        self.schedule(key, generation, 0, now);
+    }
+
+    /// Resolves an item, cancelling all of its remaining levels.
+    /// Returns 'false' if the item isn't escalated, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Escalator;
+    /// let escalator = Escalator::new(&[Duration::from_secs(60)]);
+    /// escalator.escalate(1, "unpaid invoice");
+    /// assert!(escalator.resolve(&1));
+    /// assert!(!escalator.resolve(&1));
+    /// ```
+    pub fn resolve(&self, key: &K) -> bool {
+        self.escalations_mutex().entries.remove(key).is_some()
+    }
+
+    /// Retrieves the next due escalation, waiting if necessary until one is available.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Escalator;
+    /// let escalator = Escalator::new(&[Duration::from_millis(1)]);
+    /// escalator.escalate(1, "unpaid invoice");
+    /// assert_eq!("unpaid invoice", escalator.take().item);
+    /// ```
+    pub fn take(&self) -> Escalation<K, T> {
+        loop {
+            if let Some(escalation) = self.check(self.queue.take().data) {
+                return escalation;
+            }
+        }
+    }
+
+    /// Retrieves the next due escalation, waiting if necessary until one is available or the
+    /// specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no escalation is due within the specified wait time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Escalator;
+    /// let escalator = Escalator::<u64, &str>::new(&[Duration::from_secs(60)]);
+    /// assert!(escalator.poll(Duration::from_millis(1)).is_none());
+    /// ```
+    pub fn poll(&self, timeout: Duration) -> Option<Escalation<K, T>> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let remaining = deadline.map_or(Duration::MAX, |d| {
+                d.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return None;
+            }
+            if let Some(escalation) = self.check(self.queue.poll(remaining)?.data) {
+                return Some(escalation);
+            }
+        }
+    }
+
+    /// Returns the number of unresolved items which haven't reached their last level yet.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Escalator;
+    /// let escalator = Escalator::<u64, &str>::new(&[Duration::from_secs(60)]);
+    /// assert_eq!(0, escalator.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.escalations_mutex().entries.len()
+    }
+
+    fn escalations_mutex(&self) -> MutexGuard<'_, Escalations<K, T>> {
+        self.escalations.lock().expect("Escalator lock poisoned")
+    }
+
+    // only the next level of an item is queued, the following one is scheduled once it's delivered
+    fn schedule(&self, key: K, generation: u64, level: usize, started: Instant) {
+        self.queue.add(DelayItem::new(
+            (key, generation, level),
+            started + self.levels[level],
+        ));
+    }
+
+    // delivers a due level unless its item was resolved or replaced in the meantime
+    fn check(&self, (key, generation, level): (K, u64, usize)) -> Option<Escalation<K, T>> {
+        let mut escalations = self.escalations_mutex();
+        let open = match escalations.entries.get(&key) {
+            Some(open) if open.generation == generation => open,
+            _ => return None,
+        };
+        let (item, started) = (open.item.clone(), open.started);
+        if level + 1 < self.levels.len() {
+            self.schedule(key.clone(), generation, level + 1, started);
+        } else {
+            escalations.entries.remove(&key);
+        }
+        Some(Escalation { key, item, level })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::escalator::{Escalation, Escalator};
+
+    #[test]
+    fn should_poll_with_unrepresentable_timeout() {
+        let escalator = Escalator::new(&[Duration::ZERO]);
+        escalator.escalate(1, "remind");
+        assert_eq!(
+            Some((1, 0)),
+            escalator.poll(Duration::MAX).map(|e| (e.key, e.level))
+        );
+    }
+
+    #[test]
+    fn should_deliver_increasing_levels_until_resolved() {
+        let escalator = Escalator::new(&[
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            Duration::from_millis(30),
+        ]);
+        let start = Instant::now();
+        escalator.escalate(1, "remind");
+        escalator.escalate(2, "remind");
+
+        let first: Vec<_> = (0..2).map(|_| escalator.take()).collect();
+        assert_eq!(
+            vec![0, 0],
+            first.iter().map(|e| e.level).collect::<Vec<_>>()
+        );
+        assert!(escalator.resolve(&2));
+        let second = escalator.take();
+        assert_eq!((1, 1), (second.key, second.level));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        let last = escalator.take();
+        assert_eq!(
+            Escalation {
+                key: 1,
+                item: "remind",
+                level: 2
+            },
+            last
+        );
+
+        assert_eq!(0, escalator.size());
+        assert!(escalator.poll(Duration::from_millis(20)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Escalation levels must be increasing")]
+    fn should_reject_unordered_levels() {
+        Escalator::<u64, ()>::new(&[Duration::from_secs(2), Duration::from_secs(1)]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+
+/// A publisher cloning every added item into all of its subscribed delay queues.
+/// Each subscriber can be registered with a delay offset which is added to the item delay
+/// before insertion, so independent consumers can be notified at different times.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, FanOut};
+/// let first = Arc::new(BlockingDelayQueue::new_unbounded());
+/// let second = Arc::new(BlockingDelayQueue::new_unbounded());
+/// let fan_out = FanOut::new();
+/// fan_out.subscribe(first.clone(), Duration::ZERO);
+/// fan_out.subscribe(second.clone(), Duration::from_millis(10));
+/// fan_out.add(DelayItem::new("notification", Instant::now()));
+/// assert_eq!("notification", first.take().data);
+/// assert_eq!("notification", second.take().data);
+/// ```
+pub struct FanOut<T> {
+    subscribers: Mutex<Vec<Subscriber<T>>>,
+}
+
+struct Subscriber<T> {
+    queue: Arc<BlockingDelayQueue<DelayItem<T>>>,
+    offset: Duration,
+}
+
+impl<T> Clone for Subscriber<T> {
+    fn clone(&self) -> Self {
+        Subscriber {
+            queue: self.queue.clone(),
+            offset: self.offset,
+        }
+    }
+}
+
+impl<T> Default for FanOut<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FanOut<T>
+where
+    T: Clone,
+{
+    /// Creates a new fan-out publisher without subscribers.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::FanOut;
+    /// let fan_out = FanOut::<&str>::new();
+    /// ```
+    pub fn new() -> Self {
+        FanOut {
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers a queue receiving a copy of every subsequently added item, delayed by the
+    /// provided offset.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, FanOut};
+    /// let fan_out = FanOut::<&str>::new();
+    /// fan_out.subscribe(Arc::new(BlockingDelayQueue::new_unbounded()), Duration::from_secs(1));
+    /// ```
+    pub fn subscribe(&self, queue: Arc<BlockingDelayQueue<DelayItem<T>>>, offset: Duration) {
+        self.subscribers_mutex().push(Subscriber { queue, offset });
+    }
+
+    /// Removes every subscription of the provided queue, so it no longer receives added items
+    /// and can be dropped by its owner.
+    /// Returns 'true' if the queue was subscribed.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, FanOut};
+    /// let fan_out = FanOut::<&str>::new();
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// fan_out.subscribe(queue.clone(), Duration::ZERO);
+    /// assert!(fan_out.unsubscribe(&queue));
+    /// assert_eq!(0, fan_out.size());
+    /// ```
+    pub fn unsubscribe(&self, queue: &Arc<BlockingDelayQueue<DelayItem<T>>>) -> bool {
+        let mut subscribers = self.subscribers_mutex();
+        let before = subscribers.len();
+        subscribers.retain(|s| !Arc::ptr_eq(&s.queue, queue));
+        subscribers.len() < before
+    }
+
+    /// Adds a copy of an element to every subscribed queue, waiting if necessary until space
+    /// becomes available in each of them.
+    ///
+    /// # Panics
+    /// Panics if the item delay plus a subscriber offset can't be represented, before the item
+    /// is added to any queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, FanOut};
+    /// let fan_out = FanOut::new();
+    /// fan_out.subscribe(Arc::new(BlockingDelayQueue::new_unbounded()), Duration::ZERO);
+    /// fan_out.add(DelayItem::new(123, Instant::now()));
+    /// ```
+    pub fn add(&self, e: DelayItem<T>) {
+        // snapshot subscribers so a blocked add doesn't prevent new subscriptions
+        let subscribers = self.subscribers_mutex().clone();
+        let delays = subscribers
+            .iter()
+            .map(|s| {
+                e.delay
+                    .checked_add(s.offset)
+                    .expect("Subscriber offset overflows the item delay")
+            })
+            .collect::<Vec<_>>();
+        for (s, delay) in subscribers.into_iter().zip(delays) {
+            s.queue.add(DelayItem::new(e.data.clone(), delay));
+        }
+    }
+
+    /// Returns the number of subscribed queues.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::FanOut;
+    /// let fan_out = FanOut::<&str>::new();
+    /// println!("{}", fan_out.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.subscribers_mutex().len()
+    }
+
+    fn subscribers_mutex(&self) -> MutexGuard<'_, Vec<Subscriber<T>>> {
+        self.subscribers.lock().expect("Fan-out lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::fan_out::FanOut;
+
+    #[test]
+    fn should_clone_item_into_all_subscribers() {
+        let first = Arc::new(BlockingDelayQueue::new_unbounded());
+        let second = Arc::new(BlockingDelayQueue::new_unbounded());
+        let fan_out = FanOut::new();
+        fan_out.subscribe(first.clone(), Duration::ZERO);
+        fan_out.subscribe(second.clone(), Duration::ZERO);
+
+        fan_out.add(DelayItem::new(1, Instant::now()));
+
+        assert_eq!(2, fan_out.size());
+        assert_eq!(1, first.take().data);
+        assert_eq!(1, second.take().data);
+    }
+
+    #[test]
+    fn should_apply_subscriber_offset() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let fan_out = FanOut::new();
+        let offset = Duration::from_millis(50);
+        fan_out.subscribe(queue.clone(), offset);
+
+        let now = Instant::now();
+        fan_out.add(DelayItem::new(1, now));
+
+        assert_eq!(now + offset, queue.take().delay);
+    }
+
+    #[test]
+    fn should_stop_adding_to_unsubscribed_queue() {
+        let first = Arc::new(BlockingDelayQueue::new_unbounded());
+        let second = Arc::new(BlockingDelayQueue::new_unbounded());
+        let fan_out = FanOut::new();
+        fan_out.subscribe(first.clone(), Duration::ZERO);
+        fan_out.subscribe(second.clone(), Duration::ZERO);
+
+        assert!(fan_out.unsubscribe(&first));
+        assert!(!fan_out.unsubscribe(&first));
+        fan_out.add(DelayItem::new(1, Instant::now()));
+
+        assert!(first.is_empty());
+        assert_eq!(1, second.take().data);
+    }
+
+    #[test]
+    fn should_reject_overflowing_offset_before_adding() {
+        let first = Arc::new(BlockingDelayQueue::new_unbounded());
+        let second = Arc::new(BlockingDelayQueue::new_unbounded());
+        let fan_out = FanOut::new();
+        fan_out.subscribe(first.clone(), Duration::ZERO);
+        fan_out.subscribe(second.clone(), Duration::MAX);
+
+        let added = panic::catch_unwind(AssertUnwindSafe(|| {
+            fan_out.add(DelayItem::new(1, Instant::now()))
+        }));
+
+        assert!(added.is_err());
+        assert!(first.is_empty());
+        assert!(second.is_empty());
+    }
+}
@@ -0,0 +1,43 @@
+//! A process-wide timer service for programs which just need to run something later without
+//! constructing and passing around an executor. Requires the `global` feature.
+//!
+//! The shared [DelayedExecutor](crate::DelayedExecutor) is started on first use and lives for
+//! the rest of the process, running callbacks on a thread named 'global-delayed-executor'.
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::delayed_executor::{DelayedExecutor, TimerHandle};
+use crate::thread_config::ThreadConfig;
+
+static EXECUTOR: OnceLock<DelayedExecutor> = OnceLock::new();
+
+/// Schedules the provided callback to run on the shared executor after the delay, see
+/// [DelayedExecutor::spawn_timer](crate::DelayedExecutor::spawn_timer).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::mpsc;
+/// use std::time::Duration;
+/// use blocking_delay_queue::global;
+/// let (tx, rx) = mpsc::channel();
+/// global::schedule(Duration::from_millis(10), move || tx.send("later").unwrap());
+/// assert_eq!("later", rx.recv().unwrap());
+/// ```
+pub fn schedule(delay: Duration, f: impl FnOnce() + Send + 'static) -> TimerHandle {
+    executor().spawn_timer(delay, f)
+}
+
+/// Returns the shared executor, starting it if necessary.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use blocking_delay_queue::global;
+/// println!("{} timers scheduled", global::executor().size());
+/// ```
+pub fn executor() -> &'static DelayedExecutor {
+    EXECUTOR.get_or_init(|| {
+        DelayedExecutor::new_with_thread_config(&ThreadConfig::new().name_prefix("global"))
+    })
+}
@@ -0,0 +1,219 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+use crate::lazy_timeouts::{Check, LazyTimeouts};
+
+/// Idle timeouts of network connections, built for timers which are reset on every read or write.
+/// Resetting a timer only records the activity time; instead of being rescheduled on every reset,
+/// a connection's pending check is rescheduled once it fires and finds recent activity, the same
+/// way [Watchdog](crate::Watchdog) handles heartbeats.
+/// This keeps the queue, a plain binary heap, at one entry per connection no matter how often
+/// timers are reset.
+/// Idle connections are removed and delivered to the consumer calling ['take'] / ['poll'].
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::IdleTimeouts;
+/// let timeouts = IdleTimeouts::new(Duration::from_millis(10));
+/// timeouts.register("conn-1");
+/// timeouts.touch(&"conn-1");
+/// assert_eq!("conn-1", timeouts.take());
+/// ```
+pub struct IdleTimeouts<K> {
+    queue: BlockingDelayQueue<DelayItem<(K, u64)>>,
+    connections: LazyTimeouts<K>,
+    idle_timeout: Duration,
+}
+
+impl<K> IdleTimeouts<K>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates new idle timeouts treating connections as idle after the provided timeout
+    /// without activity.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::<u64>::new(Duration::from_secs(60));
+    /// ```
+    pub fn new(idle_timeout: Duration) -> Self {
+        IdleTimeouts {
+            queue: BlockingDelayQueue::new_unbounded(),
+            connections: LazyTimeouts::new(),
+            idle_timeout,
+        }
+    }
+
+    /// Starts tracking a connection as active now, replacing a connection with the same id.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::new(Duration::from_secs(60));
+    /// timeouts.register(1);
+    /// ```
+    pub fn register(&self, id: K) {
+        let (generation, at) =
+            self.connections
+                .start(id.clone(), self.idle_timeout, Instant::now());
+        self.queue.add(DelayItem::new((id, generation), at));
+    }
+
+    /// Records activity on a connection, resetting its idle timeout.
+    /// Returns 'false' if the connection isn't tracked, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::new(Duration::from_secs(60));
+    /// timeouts.register(1);
+    /// assert!(timeouts.touch(&1));
+    /// ```
+    pub fn touch(&self, id: &K) -> bool {
+        self.connections.touch(id, Instant::now())
+    }
+
+    /// Stops tracking a connection, e.g. because it was closed.
+    /// Returns 'false' if the connection isn't tracked, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::new(Duration::from_secs(60));
+    /// timeouts.register(1);
+    /// assert!(timeouts.remove(&1));
+    /// ```
+    pub fn remove(&self, id: &K) -> bool {
+        self.connections.remove(id)
+    }
+
+    /// Retrieves and removes the next idle connection, waiting if necessary until one is available.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::new(Duration::from_millis(1));
+    /// timeouts.register(1);
+    /// assert_eq!(1, timeouts.take());
+    /// ```
+    pub fn take(&self) -> K {
+        loop {
+            if let Some(id) = self.check(self.queue.take().data) {
+                return id;
+            }
+        }
+    }
+
+    /// Retrieves and removes the next idle connection, waiting if necessary until one is
+    /// available or the specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no connection becomes idle within the specified wait time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::<u64>::new(Duration::from_secs(60));
+    /// assert!(timeouts.poll(Duration::from_millis(1)).is_none());
+    /// ```
+    pub fn poll(&self, timeout: Duration) -> Option<K> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let remaining = deadline.map_or(Duration::MAX, |d| {
+                d.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return None;
+            }
+            if let Some(id) = self.check(self.queue.poll(remaining)?.data) {
+                return Some(id);
+            }
+        }
+    }
+
+    /// Returns the number of tracked connections.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::IdleTimeouts;
+    /// let timeouts = IdleTimeouts::<u64>::new(Duration::from_secs(60));
+    /// assert_eq!(0, timeouts.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.connections.len()
+    }
+
+    // expires a connection whose check fired, or defers the check if it was active in the meantime
+    fn check(&self, (id, generation): (K, u64)) -> Option<K> {
+        match self.connections.check(&id, generation, Instant::now()) {
+            Check::Expired => Some(id),
+            Check::Reschedule(at) => {
+                self.queue.add(DelayItem::new((id, generation), at));
+                None
+            }
+            Check::Stale => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::idle_timeouts::IdleTimeouts;
+
+    #[test]
+    fn should_expire_connections_without_activity() {
+        let timeouts = IdleTimeouts::new(Duration::from_millis(50));
+        timeouts.register(1);
+        timeouts.register(2);
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(100) {
+            assert!(timeouts.touch(&1));
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(Some(2), timeouts.poll(Duration::from_millis(1)));
+        assert!(timeouts.poll(Duration::from_millis(20)).is_none());
+        assert_eq!(1, timeouts.take());
+        assert!(start.elapsed() >= Duration::from_millis(140));
+        assert_eq!(0, timeouts.size());
+    }
+
+    #[test]
+    fn should_keep_one_queue_entry_per_connection() {
+        let timeouts = IdleTimeouts::new(Duration::from_secs(60));
+        timeouts.register(1);
+        for _ in 0..10_000 {
+            timeouts.touch(&1);
+        }
+        assert_eq!(1, timeouts.queue.size());
+        assert!(timeouts.remove(&1));
+        assert!(!timeouts.touch(&1));
+    }
+
+    #[test]
+    fn should_poll_with_unrepresentable_timeout() {
+        let timeouts = IdleTimeouts::new(Duration::ZERO);
+        timeouts.register(1);
+        assert_eq!(Some(1), timeouts.poll(Duration::MAX));
+    }
+}
@@ -0,0 +1,277 @@
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+
+#[derive(Serialize)]
+struct LineRef<'a, D> {
+    data: &'a D,
+    deadline: String,
+}
+
+#[derive(Deserialize)]
+struct Line<D> {
+    data: D,
+    deadline: String,
+}
+
+impl<D> BlockingDelayQueue<DelayItem<D>>
+where
+    D: Serialize + DeserializeOwned,
+{
+    /// Writes all elements of this queue in JSON Lines format, one `{"data": .., "deadline": ..}`
+    /// object per line ordered by deadline, without removing them.
+    /// Deadlines are converted to wall clock time and written as ISO-8601 / RFC 3339 UTC timestamps
+    /// with microsecond precision. Returns the number of written elements.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::new_unbounded();
+    /// queue.add(DelayItem::new("reminder".to_string(), Instant::now()));
+    /// let mut out = Vec::new();
+    /// assert_eq!(1, queue.export_jsonl(&mut out).unwrap());
+    /// ```
+    pub fn export_jsonl(&self, mut writer: impl Write) -> io::Result<usize> {
+        let (now, wall_now) = (Instant::now(), SystemTime::now());
+        let mut lines = self
+            .map_elements(|e| {
+                let line = LineRef {
+                    data: &e.data,
+                    deadline: format_rfc3339(to_wall_clock(e.delay, now, wall_now)),
+                };
+                serde_json::to_string(&line).map(|json| (e.delay, json))
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        lines.sort_by_key(|line| line.0);
+
+        for (_, json) in lines.iter() {
+            writeln!(writer, "{}", json)?;
+        }
+        Ok(lines.len())
+    }
+
+    /// Adds the elements read from JSON Lines in the format written by ['export_jsonl'].
+    /// Empty lines are skipped, deadlines may use any UTC offset and deadlines in the past are
+    /// expired right away. Returns the number of added elements.
+    /// Like ['add'], this blocks while a bounded queue is full.
+    ///
+    /// # Errors
+    /// Returns an error of kind [InvalidData](std::io::ErrorKind::InvalidData) on a malformed
+    /// line, in which case the elements of all previous lines have already been added.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::<DelayItem<String>>::new_unbounded();
+    /// let jsonl = r#"{"data": "reminder", "deadline": "2020-01-01T00:00:00Z"}"#;
+    /// assert_eq!(1, queue.import_jsonl(jsonl.as_bytes()).unwrap());
+    /// assert_eq!("reminder", queue.take().data);
+    /// ```
+    pub fn import_jsonl(&self, reader: impl BufRead) -> io::Result<usize> {
+        let mut imported = 0;
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line: Line<D> = serde_json::from_str(&line)?;
+            let deadline = parse_rfc3339(&line.deadline).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid deadline '{}'", line.deadline),
+                )
+            })?;
+            let delay = to_instant(deadline, Instant::now(), SystemTime::now());
+            self.add(DelayItem::new(line.data, delay));
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
+
+fn to_wall_clock(instant: Instant, now: Instant, wall_now: SystemTime) -> SystemTime {
+    if instant >= now {
+        wall_now + (instant - now)
+    } else {
+        wall_now - (now - instant)
+    }
+}
+
+// deadlines too far in the past for the monotonic clock are clamped to now, they're expired either way
+fn to_instant(wall: SystemTime, now: Instant, wall_now: SystemTime) -> Instant {
+    match wall.duration_since(wall_now) {
+        Ok(ahead) => now + ahead,
+        Err(behind) => now.checked_sub(behind.duration()).unwrap_or(now),
+    }
+}
+
+fn format_rfc3339(time: SystemTime) -> String {
+    let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => {
+            let d = e.duration();
+            match d.subsec_nanos() {
+                0 => (-(d.as_secs() as i64), 0),
+                n => (-(d.as_secs() as i64) - 1, 1_000_000_000 - n),
+            }
+        }
+    };
+    let (days, secs_of_day) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        nanos / 1000
+    )
+}
+
+// accepts 'YYYY-MM-DDTHH:MM:SS[.fraction](Z|±HH:MM)'
+fn parse_rfc3339(s: &str) -> Option<SystemTime> {
+    let b = s.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let num = |from: usize, to: usize| -> Option<i64> {
+        let digits = s.get(from..to)?;
+        if digits.bytes().all(|c| c.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    };
+    let (year, month, day) = (num(0, 4)?, num(5, 7)?, num(8, 10)?);
+    let (hour, minute, second) = (num(11, 13)?, num(14, 16)?, num(17, 19)?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut i = 19;
+    let mut nanos = 0u32;
+    if b[i] == b'.' {
+        let digits = b[i + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 || digits > 9 {
+            return None;
+        }
+        nanos = num(i + 1, i + 1 + digits)? as u32 * 10u32.pow(9 - digits as u32);
+        i += 1 + digits;
+    }
+    let offset = match &s[i..] {
+        "Z" | "z" => 0,
+        tz if tz.len() == 6 && tz.as_bytes()[3] == b':' => {
+            let sign = match tz.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            sign * (num(i + 1, i + 3)? * 3600 + num(i + 4, i + 6)? * 60)
+        }
+        _ => return None,
+    };
+
+    // a leap second is folded into the following second
+    let secs =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    let subsec = Duration::from_nanos(u64::from(nanos));
+    if secs >= 0 {
+        Some(UNIX_EPOCH + Duration::from_secs(secs as u64) + subsec)
+    } else {
+        Some(UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()) + subsec)
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since the unix epoch of a proleptic gregorian date, see
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use std::time::{Duration, Instant, UNIX_EPOCH};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::jsonl::{format_rfc3339, parse_rfc3339};
+
+    #[test]
+    fn should_format_and_parse_rfc3339() {
+        let leap_day = UNIX_EPOCH + Duration::from_micros(1_709_210_096_500_000);
+        assert_eq!("2024-02-29T12:34:56.500000Z", format_rfc3339(leap_day));
+        assert_eq!(Some(leap_day), parse_rfc3339("2024-02-29T13:34:56.5+01:00"));
+        assert_eq!(
+            "1969-12-31T23:59:59.750000Z",
+            format_rfc3339(UNIX_EPOCH - Duration::from_millis(250))
+        );
+        assert_eq!(None, parse_rfc3339("2023-02-29T00:00:00Z"));
+        assert_eq!(None, parse_rfc3339("2024-02-29 12:34"));
+    }
+
+    #[test]
+    fn should_export_and_import_schedule() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(DelayItem::new(vec![2], now + Duration::from_secs(60)));
+        queue.add(DelayItem::new(vec![1], now));
+
+        let mut out = Vec::new();
+        assert_eq!(2, queue.export_jsonl(&mut out).unwrap());
+        assert_eq!(2, queue.size());
+        let lines: Vec<_> = out.lines().map(|l| l.unwrap()).collect();
+        assert!(lines[0].starts_with(r#"{"data":[1],"deadline":""#));
+
+        let restored = BlockingDelayQueue::<DelayItem<Vec<u8>>>::new_unbounded();
+        assert_eq!(2, restored.import_jsonl(&out[..]).unwrap());
+        assert_eq!(vec![1], restored.take().data);
+        assert_eq!(1, restored.count_due_within(Duration::from_secs(61)));
+        assert_eq!(0, restored.count_due_within(Duration::from_secs(58)));
+        assert!(restored.import_jsonl(&b"{\"data\":[1]}"[..]).is_err());
+    }
+}
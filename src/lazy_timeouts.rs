@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// the outcome of a scheduled check of a key
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Check {
+    // no activity within the timeout, the key was removed
+    Expired,
+    // active since the check was scheduled, so it has to be checked again at the provided time
+    Reschedule(Instant),
+    // the key was removed or restarted since the check was scheduled
+    Stale,
+}
+
+struct Activity {
+    generation: u64,
+    timeout: Duration,
+    last_active: Instant,
+}
+
+struct Keys<K> {
+    activities: HashMap<K, Activity>,
+    generation: u64,
+}
+
+// timeouts of keys which are reset far more often than they expire, e.g. on every heartbeat or
+// every read of a connection. Resetting a key only records the activity time; its single check is
+// scheduled by the owner in a plain delay queue and rescheduled once it fires and finds recent
+// activity, so resets never touch the queue.
+pub(crate) struct LazyTimeouts<K> {
+    keys: Mutex<Keys<K>>,
+}
+
+impl<K> LazyTimeouts<K>
+where
+    K: Eq + Hash,
+{
+    pub(crate) fn new() -> Self {
+        LazyTimeouts {
+            keys: Mutex::new(Keys {
+                activities: HashMap::new(),
+                generation: 0,
+            }),
+        }
+    }
+
+    // starts or restarts the timeout of the key, returning the generation and time of the check
+    // to schedule
+    pub(crate) fn start(&self, key: K, timeout: Duration, now: Instant) -> (u64, Instant) {
+        let mut keys = self.keys_mutex();
+        keys.generation += 1;
+        let generation = keys.generation;
+        let activity = Activity {
+            generation,
+            timeout,
+            last_active: now,
+        };
+        keys.activities.insert(key, activity);
+        (generation, now + timeout)
+    }
+
+    // returns 'false' if the key isn't tracked
+    pub(crate) fn touch(&self, key: &K, now: Instant) -> bool {
+        match self.keys_mutex().activities.get_mut(key) {
+            Some(activity) => {
+                activity.last_active = now;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // returns 'false' if the key isn't tracked
+    pub(crate) fn remove(&self, key: &K) -> bool {
+        self.keys_mutex().activities.remove(key).is_some()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.keys_mutex().activities.len()
+    }
+
+    // handles a check of the key scheduled for the provided generation which fired
+    pub(crate) fn check(&self, key: &K, generation: u64, now: Instant) -> Check {
+        let mut keys = self.keys_mutex();
+        let deadline = match keys.activities.get(key) {
+            Some(a) if a.generation == generation => a.last_active + a.timeout,
+            _ => return Check::Stale,
+        };
+        if deadline <= now {
+            keys.activities.remove(key);
+            Check::Expired
+        } else {
+            Check::Reschedule(deadline)
+        }
+    }
+
+    fn keys_mutex(&self) -> MutexGuard<'_, Keys<K>> {
+        self.keys.lock().expect("Timeouts lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::lazy_timeouts::{Check, LazyTimeouts};
+
+    #[test]
+    fn should_reschedule_touched_keys_and_skip_stale_checks() {
+        let timeouts = LazyTimeouts::new();
+        let start = Instant::now();
+        let timeout = Duration::from_secs(10);
+        let (stale, _) = timeouts.start(1, timeout, start);
+        let (generation, at) = timeouts.start(1, timeout, start);
+        assert_eq!(start + timeout, at);
+        assert!(timeouts.touch(&1, start + Duration::from_secs(5)));
+
+        assert_eq!(Check::Stale, timeouts.check(&1, stale, at));
+        let rescheduled = start + Duration::from_secs(15);
+        assert_eq!(
+            Check::Reschedule(rescheduled),
+            timeouts.check(&1, generation, at)
+        );
+        assert_eq!(Check::Expired, timeouts.check(&1, generation, rescheduled));
+        assert_eq!(0, timeouts.len());
+        assert!(!timeouts.touch(&1, rescheduled));
+    }
+}
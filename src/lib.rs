@@ -1,6 +1,122 @@
 //! A thread safe blocking delay queue in which an element can only be taken when its delay has expired.
+#[cfg(feature = "audit")]
+mod audit;
+pub mod bench_support;
 mod blocking_delay_queue;
+mod budgeted;
+mod builder;
+mod calibration;
+mod cancel_scope;
+mod config;
+mod deadline_barrier;
+mod deadline_wait;
+#[cfg(feature = "debug-checks")]
+mod debug_checks;
+mod delay_cell;
 mod delay_item;
+mod delayed_executor;
+mod diagnostics;
+mod escalator;
+mod fan_out;
+#[cfg(feature = "global")]
+pub mod global;
+mod idle_timeouts;
+#[cfg(feature = "serde")]
+mod jsonl;
+mod lazy_timeouts;
+mod listener;
+mod manual_delay_queue;
+mod mapped_queue;
+mod memory;
+mod multi_lane_delay_queue;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pipeline;
+mod pump;
+mod quota;
+mod registry;
+mod router;
+mod scheduled_task;
+mod session_manager;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod thread_config;
+mod ticker;
+mod timed_latch;
+mod timeout_map;
+mod trace_context;
+mod ttl_queue;
+mod watchdog;
 
+#[cfg(feature = "audit")]
+pub use self::audit::{AuditOperation, AuditRecord, AuditSink};
 pub use self::blocking_delay_queue::BlockingDelayQueue;
+pub use self::budgeted::Budgeted;
+pub use self::builder::QueueBuilder;
+pub use self::calibration::{calibrate, TimingReport};
+pub use self::cancel_scope::CancelScope;
+pub use self::config::QueueConfig;
+pub use self::deadline_barrier::DeadlineBarrier;
+pub use self::delay_cell::DelayCell;
 pub use self::delay_item::{DelayItem, Delayed};
+pub use self::delayed_executor::{CompletionHandle, DelayedExecutor, Panicked, TimerHandle};
+pub use self::diagnostics::Diagnostics;
+pub use self::escalator::{Escalation, Escalator};
+pub use self::fan_out::FanOut;
+pub use self::idle_timeouts::IdleTimeouts;
+pub use self::listener::Outcome;
+pub use self::manual_delay_queue::ManualDelayQueue;
+pub use self::mapped_queue::MappedQueue;
+pub use self::memory::Eviction;
+pub use self::multi_lane_delay_queue::MultiLaneDelayQueue;
+pub use self::pipeline::{Pipeline, PipelineBuilder};
+pub use self::pump::PumpHandle;
+pub use self::quota::QuotaExceeded;
+pub use self::registry::QueueRegistry;
+pub use self::router::Router;
+pub use self::scheduled_task::{ScheduledTask, TaskQueue};
+pub use self::session_manager::SessionManager;
+pub use self::thread_config::ThreadConfig;
+pub use self::ticker::{MissedTicks, Ticker};
+pub use self::timed_latch::TimedLatch;
+pub use self::timeout_map::TimeoutMap;
+pub use self::trace_context::{TraceContext, Traced};
+pub use self::ttl_queue::TtlQueue;
+pub use self::watchdog::Watchdog;
+
+// compile time guarantees that the thread safety documented on the queue holds for every type
+// built on it, the negative cases are covered by the compile_fail examples of BlockingDelayQueue
+#[allow(dead_code)]
+fn assert_thread_safety<T, R, K>()
+where
+    T: Send,
+    R: Send,
+    K: Eq + std::hash::Hash + Clone + Send + 'static,
+{
+    fn send<S: Send>() {}
+    fn send_sync<S: Send + Sync>() {}
+    send_sync::<BlockingDelayQueue<T>>();
+    send_sync::<DeadlineBarrier>();
+    send_sync::<DelayCell<T>>();
+    send_sync::<CompletionHandle<T>>();
+    send_sync::<DelayedExecutor>();
+    send_sync::<TimerHandle>();
+    send_sync::<Escalator<K, T>>();
+    send_sync::<FanOut<T>>();
+    send_sync::<IdleTimeouts<K>>();
+    send_sync::<MultiLaneDelayQueue<T>>();
+    send_sync::<Pipeline<T>>();
+    send_sync::<PumpHandle>();
+    send_sync::<QueueRegistry>();
+    send_sync::<Router<T, R>>();
+    send_sync::<SessionManager<K>>();
+    send_sync::<TaskQueue>();
+    send_sync::<TimeoutMap<K, T>>();
+    send_sync::<TtlQueue<T>>();
+    send_sync::<Watchdog<K>>();
+    send_sync::<ThreadConfig>();
+    send_sync::<Ticker>();
+    send_sync::<TimedLatch>();
+    send::<ManualDelayQueue<T>>();
+    send::<QueueBuilder<T>>();
+}
@@ -0,0 +1,11 @@
+/// The outcome reported to a per-item listener, see
+/// [add_with_listener](crate::BlockingDelayQueue::add_with_listener).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The item was taken from the queue by a consumer.
+    Delivered,
+    /// The item was removed from the queue without being delivered.
+    Cancelled,
+}
+
+pub(crate) type Listener = Box<dyn FnOnce(Outcome) + Send>;
@@ -0,0 +1,156 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+
+use crate::delay_item::Delayed;
+
+/// A single-threaded delay queue driven by a virtual clock which only moves when
+/// ['advance'] is called, for game loops and discrete-event simulations controlling time
+/// themselves instead of blocking.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+/// let mut queue = ManualDelayQueue::new();
+/// let start = queue.now();
+/// queue.add(DelayItem::new("spawn", start + Duration::from_millis(16)));
+/// assert!(queue.advance(Duration::from_millis(10)).is_empty());
+/// assert_eq!("spawn", queue.advance(Duration::from_millis(10))[0].data);
+/// ```
+pub struct ManualDelayQueue<T> {
+    heap: BinaryHeap<Reverse<T>>,
+    now: Instant,
+}
+
+impl<T> Default for ManualDelayQueue<T>
+where
+    T: Delayed + Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ManualDelayQueue<T>
+where
+    T: Delayed + Ord,
+{
+    /// Creates a new queue whose virtual clock starts at the current time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+    /// let queue = ManualDelayQueue::<DelayItem<&str>>::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// Creates a new queue whose virtual clock starts at the provided time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+    /// let queue = ManualDelayQueue::<DelayItem<&str>>::starting_at(Instant::now());
+    /// ```
+    pub fn starting_at(now: Instant) -> Self {
+        ManualDelayQueue {
+            heap: BinaryHeap::new(),
+            now,
+        }
+    }
+
+    /// Returns the current virtual time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+    /// let queue = ManualDelayQueue::<DelayItem<&str>>::new();
+    /// let now = queue.now();
+    /// ```
+    pub fn now(&self) -> Instant {
+        self.now
+    }
+
+    /// Adds an element to this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+    /// let mut queue = ManualDelayQueue::new();
+    /// let now = queue.now();
+    /// queue.add(DelayItem::new(123, now));
+    /// ```
+    pub fn add(&mut self, e: T) {
+        self.heap.push(Reverse(e));
+    }
+
+    /// Moves the virtual clock forward by the provided duration and returns, in delay order,
+    /// all elements whose delay expired up to the new virtual time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+    /// let mut queue = ManualDelayQueue::new();
+    /// let now = queue.now();
+    /// queue.add(DelayItem::new(123, now));
+    /// assert_eq!(1, queue.advance(Duration::ZERO).len());
+    /// ```
+    pub fn advance(&mut self, by: Duration) -> Vec<T> {
+        self.now += by;
+        let mut expired = Vec::new();
+        while self.heap.peek().is_some_and(|e| e.0.delay() <= self.now) {
+            expired.push(self.heap.pop().unwrap().0);
+        }
+        expired
+    }
+
+    /// Returns the number of elements in this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, ManualDelayQueue};
+    /// let queue = ManualDelayQueue::<DelayItem<&str>>::new();
+    /// assert_eq!(0, queue.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::delay_item::DelayItem;
+    use crate::manual_delay_queue::ManualDelayQueue;
+
+    #[test]
+    fn should_release_items_expired_within_window() {
+        let start = Instant::now();
+        let mut queue = ManualDelayQueue::starting_at(start);
+        queue.add(DelayItem::new(3, start + Duration::from_secs(30)));
+        queue.add(DelayItem::new(1, start + Duration::from_secs(10)));
+        queue.add(DelayItem::new(2, start + Duration::from_secs(20)));
+
+        assert!(queue.advance(Duration::from_secs(5)).is_empty());
+        let expired: Vec<_> = queue
+            .advance(Duration::from_secs(20))
+            .into_iter()
+            .map(|e| e.data)
+            .collect();
+        assert_eq!(vec![1, 2], expired);
+        assert_eq!(start + Duration::from_secs(25), queue.now());
+        assert_eq!(1, queue.size());
+    }
+}
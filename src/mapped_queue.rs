@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+
+/// A consumer view of a [BlockingDelayQueue](crate::BlockingDelayQueue) applying a transformation
+/// to every element it delivers, so consumers only ever see the projected results.
+/// Producers keep adding to the underlying queue.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Instant;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, MappedQueue};
+/// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+/// let lengths = MappedQueue::new(queue.clone(), |e: DelayItem<String>| e.data.len());
+/// queue.add(DelayItem::new("payload".to_string(), Instant::now()));
+/// assert_eq!(7, lengths.take());
+/// ```
+pub struct MappedQueue<T, F> {
+    queue: Arc<BlockingDelayQueue<T>>,
+    f: F,
+}
+
+impl<T, R, F> MappedQueue<T, F>
+where
+    T: Delayed + Ord,
+    F: Fn(T) -> R,
+{
+    /// Creates a new view delivering the elements of the provided queue transformed by 'f'.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, MappedQueue};
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<u32>>::new_unbounded());
+    /// let ids = MappedQueue::new(queue, |e| e.data);
+    /// ```
+    pub fn new(queue: Arc<BlockingDelayQueue<T>>, f: F) -> Self {
+        MappedQueue { queue, f }
+    }
+
+    /// Retrieves, removes and transforms the head of the underlying queue, waiting if necessary
+    /// until an element with an expired delay is available.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, MappedQueue};
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// let ids = MappedQueue::new(queue.clone(), |e: DelayItem<u32>| e.data);
+    /// queue.add(DelayItem::new(123, Instant::now()));
+    /// assert_eq!(123, ids.take());
+    /// ```
+    pub fn take(&self) -> R {
+        self.queue.take_map(&self.f)
+    }
+
+    /// Retrieves, removes and transforms the head of the underlying queue, waiting if necessary
+    /// until an element with an expired delay is available or the specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no element is available within the specified wait time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, MappedQueue};
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// let ids = MappedQueue::new(queue, |e: DelayItem<u32>| e.data);
+    /// assert!(ids.poll(Duration::from_millis(1)).is_none());
+    /// ```
+    pub fn poll(&self, timeout: Duration) -> Option<R> {
+        self.queue.poll(timeout).map(&self.f)
+    }
+
+    /// Returns the number of elements in the underlying queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, MappedQueue};
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// let ids = MappedQueue::new(queue, |e: DelayItem<u32>| e.data);
+    /// assert_eq!(0, ids.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.queue.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::mapped_queue::MappedQueue;
+
+    #[test]
+    fn should_deliver_projected_items() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let mapped = MappedQueue::new(queue.clone(), |e: DelayItem<Vec<u8>>| e.data.len());
+        let now = Instant::now();
+        queue.add(DelayItem::new(vec![0; 3], now));
+        queue.add(DelayItem::new(vec![0; 1], now + Duration::from_millis(5)));
+
+        assert_eq!(3, mapped.take());
+        assert_eq!(Some(1), mapped.poll(Duration::from_secs(1)));
+        assert_eq!(None, mapped.poll(Duration::from_millis(1)));
+    }
+}
@@ -0,0 +1,36 @@
+/// The order in which elements are evicted once the estimated memory usage of a queue exceeds
+/// its [memory limit](crate::QueueBuilder::memory_limit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// Evicts elements with the furthest deadline first, keeping the most urgent work.
+    #[default]
+    FurthestDeadline,
+    /// Evicts elements with the nearest deadline first, keeping the work due furthest in the future.
+    NearestDeadline,
+}
+
+pub(crate) struct MemoryLimit<T> {
+    pub(crate) estimate: Box<dyn Fn(&T) -> usize + Send>,
+    pub(crate) max_bytes: usize,
+    pub(crate) eviction: Eviction,
+    pub(crate) bytes: usize,
+}
+
+impl<T> MemoryLimit<T> {
+    pub(crate) fn new(
+        max_bytes: usize,
+        estimate: impl Fn(&T) -> usize + Send + 'static,
+        eviction: Eviction,
+    ) -> Self {
+        MemoryLimit {
+            estimate: Box::new(estimate),
+            max_bytes,
+            eviction,
+            bytes: 0,
+        }
+    }
+
+    pub(crate) fn is_exceeded(&self) -> bool {
+        self.bytes > self.max_bytes
+    }
+}
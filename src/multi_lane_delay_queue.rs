@@ -0,0 +1,259 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::deadline_wait;
+use crate::delay_item::Delayed;
+
+struct Lane<T> {
+    heap: BinaryHeap<Reverse<T>>,
+    weight: i64,
+    current: i64,
+}
+
+/// An unbounded blocking delay queue split into weighted priority lanes.
+/// When heads of several lanes are expired, the next element is selected by smooth weighted
+/// round robin, so lanes with a higher weight are preferred while lanes with a lower weight
+/// still make progress.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Instant;
+/// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+/// let queue = MultiLaneDelayQueue::new(&[3, 1]);
+/// queue.add(0, DelayItem::new("high", Instant::now()));
+/// queue.add(1, DelayItem::new("low", Instant::now()));
+/// assert_eq!("high", queue.take().data);
+/// assert_eq!("low", queue.take().data);
+/// ```
+pub struct MultiLaneDelayQueue<T> {
+    lanes: Mutex<Vec<Lane<T>>>,
+    // fixed on creation, so lanes can be validated without locking
+    lane_count: usize,
+    condvar: Condvar,
+    max_wait_slice: Option<Duration>,
+}
+
+impl<T> MultiLaneDelayQueue<T>
+where
+    T: Delayed + Ord,
+{
+    /// Creates a new queue with one lane per provided weight, where a weight of '0' is treated as '1'.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+    /// let queue = MultiLaneDelayQueue::<DelayItem<&str>>::new(&[8, 2, 1]);
+    /// ```
+    pub fn new(weights: &[u32]) -> Self {
+        let lanes: Vec<_> = weights
+            .iter()
+            .map(|w| Lane {
+                heap: BinaryHeap::new(),
+                weight: i64::from((*w).max(1)),
+                current: 0,
+            })
+            .collect();
+        MultiLaneDelayQueue {
+            lane_count: lanes.len(),
+            lanes: Mutex::new(lanes),
+            condvar: Condvar::new(),
+            max_wait_slice: None,
+        }
+    }
+
+    /// Caps every single wait of a blocked consumer, like
+    /// [max_wait_slice](crate::QueueBuilder::max_wait_slice), so the lane heads are re-checked
+    /// at least once per slice.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+    /// let queue = MultiLaneDelayQueue::<DelayItem<&str>>::new(&[2, 1])
+    ///     .max_wait_slice(Duration::from_secs(1));
+    /// ```
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// Adds an element to the provided lane.
+    ///
+    /// # Panics
+    /// Panics if the lane doesn't exist, before the queue is locked, so the queue remains usable.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+    /// let queue = MultiLaneDelayQueue::new(&[2, 1]);
+    /// queue.add(1, DelayItem::new(123, Instant::now()));
+    /// ```
+    pub fn add(&self, lane: usize, e: T) {
+        assert!(
+            lane < self.lane_count,
+            "Lane {} doesn't exist, the queue has {} lanes",
+            lane,
+            self.lane_count
+        );
+        self.lanes_mutex()[lane].heap.push(Reverse(e));
+        self.condvar.notify_all();
+    }
+
+    /// Retrieves and removes the next element from the lanes, waiting if necessary until an
+    /// element with an expired delay is available in any of them.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+    /// let queue = MultiLaneDelayQueue::new(&[2, 1]);
+    /// queue.add(1, DelayItem::new(123, Instant::now()));
+    /// assert_eq!(123, queue.take().data);
+    /// ```
+    pub fn take(&self) -> T {
+        match self.wait_for_element(None) {
+            Some(e) => e,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Retrieves and removes the next element from the lanes, waiting if necessary until an
+    /// element with an expired delay is available in any of them, or the specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no element is available within the specified wait time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+    /// let queue = MultiLaneDelayQueue::<DelayItem<&str>>::new(&[2, 1]);
+    /// assert!(queue.poll(Duration::from_millis(1)).is_none());
+    /// ```
+    pub fn poll(&self, timeout: Duration) -> Option<T> {
+        self.wait_for_element(Instant::now().checked_add(timeout))
+    }
+
+    /// Returns the number of elements in all lanes.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, MultiLaneDelayQueue};
+    /// let queue = MultiLaneDelayQueue::<DelayItem<&str>>::new(&[2, 1]);
+    /// assert_eq!(0, queue.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.lanes_mutex().iter().map(|l| l.heap.len()).sum()
+    }
+
+    fn lanes_mutex(&self) -> MutexGuard<'_, Vec<Lane<T>>> {
+        self.lanes.lock().expect("Queue lock poisoned")
+    }
+
+    fn wait_for_element(&self, deadline: Option<Instant>) -> Option<T> {
+        let mut lanes = self.lanes_mutex();
+        loop {
+            let now = Instant::now();
+            if let Some(e) = Self::select(&mut lanes, now) {
+                return Some(e);
+            }
+            if deadline.is_some_and(|d| d <= now) {
+                return None;
+            }
+
+            let next_expiration = lanes
+                .iter()
+                .filter_map(|l| l.heap.peek().map(|e| e.0.delay()))
+                .min();
+            let wake_at = match (next_expiration, deadline) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            lanes =
+                deadline_wait::wait_until(&self.condvar, lanes, wake_at, now, self.max_wait_slice)
+                    .expect("Queue lock poisoned");
+        }
+    }
+
+    // smooth weighted round robin among lanes with an expired head
+    fn select(lanes: &mut [Lane<T>], now: Instant) -> Option<T> {
+        let mut total = 0;
+        let mut selected: Option<usize> = None;
+        for i in 0..lanes.len() {
+            if lanes[i].heap.peek().is_some_and(|e| e.0.delay() <= now) {
+                lanes[i].current += lanes[i].weight;
+                total += lanes[i].weight;
+                if selected.is_none_or(|s| lanes[i].current > lanes[s].current) {
+                    selected = Some(i);
+                }
+            }
+        }
+        let i = selected?;
+        lanes[i].current -= total;
+        lanes[i].heap.pop().map(|e| e.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+    use std::time::{Duration, Instant};
+
+    use crate::delay_item::DelayItem;
+    use crate::multi_lane_delay_queue::MultiLaneDelayQueue;
+
+    #[test]
+    fn should_remain_usable_after_adding_to_missing_lane() {
+        let queue = MultiLaneDelayQueue::new(&[1]);
+        let added = panic::catch_unwind(AssertUnwindSafe(|| {
+            queue.add(1, DelayItem::new("missing", Instant::now()))
+        }));
+
+        assert!(added.is_err());
+        queue.add(0, DelayItem::new("valid", Instant::now()));
+        assert_eq!("valid", queue.take().data);
+    }
+
+    #[test]
+    fn should_poll_with_unrepresentable_timeout() {
+        let queue = MultiLaneDelayQueue::new(&[1]);
+        queue.add(0, DelayItem::new("due", Instant::now()));
+        assert_eq!(Some("due"), queue.poll(Duration::MAX).map(|e| e.data));
+    }
+
+    #[test]
+    fn should_dequeue_expired_lanes_by_weight() {
+        let queue = MultiLaneDelayQueue::new(&[3, 1]);
+        let now = Instant::now();
+        for _ in 0..4 {
+            queue.add(0, DelayItem::new("high", now));
+            queue.add(1, DelayItem::new("low", now));
+        }
+
+        let first: Vec<_> = (0..4).map(|_| queue.take().data).collect();
+        assert_eq!(3, first.iter().filter(|d| **d == "high").count());
+        assert_eq!(1, first.iter().filter(|d| **d == "low").count());
+        assert_eq!(4, queue.size());
+    }
+
+    #[test]
+    fn should_not_take_unexpired_lane_head() {
+        let queue = MultiLaneDelayQueue::new(&[10, 1]);
+        queue.add(
+            0,
+            DelayItem::new(1, Instant::now() + Duration::from_secs(60)),
+        );
+        queue.add(1, DelayItem::new(2, Instant::now()));
+
+        assert_eq!(2, queue.take().data);
+        assert!(queue.poll(Duration::from_millis(5)).is_none());
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+
+impl<T> BlockingDelayQueue<T>
+where
+    T: Delayed + Ord + Send,
+{
+    /// Removes all currently expired elements and processes them in parallel on the rayon thread
+    /// pool, returning once every element was handled.
+    /// Elements expiring while the batch is processed are left for the next call, which keeps
+    /// expiry storms of thousands of elements due at once from being handled one by one.
+    /// Returns the number of processed elements.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = BlockingDelayQueue::new_unbounded();
+    /// for i in 0..100 {
+    ///     queue.add(DelayItem::new(i, Instant::now()));
+    /// }
+    /// let sum = AtomicUsize::new(0);
+    /// assert_eq!(100, queue.drain_expired_parallel(|e| {
+    ///     sum.fetch_add(e.data, Ordering::Relaxed);
+    /// }));
+    /// assert_eq!(4950, sum.into_inner());
+    /// ```
+    pub fn drain_expired_parallel(&self, handler: impl Fn(T) + Sync + Send) -> usize {
+        // the batch is released at once, without waiting for elements expiring meanwhile
+        let mut batch = Vec::new();
+        let drained = self.take_into(&mut batch, usize::MAX, Duration::ZERO);
+        batch.into_par_iter().for_each(handler);
+        drained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+
+    #[test]
+    fn should_drain_only_expired_items() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        for i in 0..1000 {
+            queue.add(DelayItem::new(i, Instant::now()));
+        }
+        queue.add(DelayItem::new(
+            1000,
+            Instant::now() + Duration::from_secs(60),
+        ));
+
+        let handled = Mutex::new(Vec::new());
+        let drained = queue.drain_expired_parallel(|e| handled.lock().unwrap().push(e.data));
+        let mut handled = handled.into_inner().unwrap();
+        handled.sort_unstable();
+
+        assert_eq!(1000, drained);
+        assert_eq!((0..1000).collect::<Vec<_>>(), handled);
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_not_chase_elements_expiring_while_draining() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        for i in 0..100 {
+            queue.add(DelayItem::new(i, Instant::now()));
+        }
+        let before = queue.size();
+
+        // every handled element is followed by one which is already expired
+        let drained = queue.drain_expired_parallel(|e| {
+            queue.add(DelayItem::new(e.data + 100, Instant::now()));
+        });
+        assert_eq!(before, drained);
+        assert_eq!(drained, queue.size());
+        assert!(queue.take().data >= 100);
+    }
+}
@@ -0,0 +1,379 @@
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+use crate::thread_config::ThreadConfig;
+use crate::trace_context::{CapturedContext, ContextCapture, TraceContext};
+
+type Handler<T> = Box<dyn Fn(T) -> Option<T> + Send>;
+
+enum Signal<T> {
+    Item(T, Option<CapturedContext>),
+    Stop,
+}
+
+struct Stage<T> {
+    queue: Arc<BlockingDelayQueue<DelayItem<Signal<T>>>>,
+    delay: Duration,
+    capture: Option<ContextCapture>,
+}
+
+impl<T> Stage<T> {
+    fn schedule(&self, e: T) {
+        let context = self.capture.as_ref().map(ContextCapture::capture);
+        self.queue.add(DelayItem::new(
+            Signal::Item(e, context),
+            Instant::now() + self.delay,
+        ));
+    }
+}
+
+/// A builder for [Pipeline](crate::Pipeline) adding its stages in order, see
+/// [builder](crate::Pipeline::builder).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::Pipeline;
+/// let pipeline = Pipeline::builder()
+///     .stage(Duration::from_secs(86_400), |invoice: u64| {
+///         println!("reminding {}", invoice);
+///         Some(invoice)
+///     })
+///     .stage(Duration::from_secs(3 * 86_400), |invoice| {
+///         println!("escalating {}", invoice);
+///         None
+///     })
+///     .build();
+/// ```
+pub struct PipelineBuilder<T> {
+    stages: Vec<(Duration, Handler<T>)>,
+    thread_config: ThreadConfig,
+    capture: Option<ContextCapture>,
+}
+
+/// A chain of delay stages, each delaying an item by its own delay before passing it to its
+/// handler, which either forwards the item to the next stage or completes it, for workflows
+/// like "send a reminder after one day, escalate after three more days".
+/// Every stage runs its handler on a dedicated thread. When the pipeline is dropped, stages are
+/// stopped from first to last, so items already handed on by an earlier stage are still handled
+/// by the later ones if due; items which aren't due yet are discarded.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::mpsc;
+/// use std::time::Duration;
+/// use blocking_delay_queue::Pipeline;
+/// let (tx, rx) = mpsc::channel();
+/// let pipeline = Pipeline::builder()
+///     .stage(Duration::from_millis(10), |e: u32| Some(e + 1))
+///     .stage(Duration::from_millis(10), move |e| {
+///         tx.send(e).unwrap();
+///         None
+///     })
+///     .build();
+/// pipeline.submit(1);
+/// assert_eq!(2, rx.recv().unwrap());
+/// ```
+pub struct Pipeline<T> {
+    stages: Vec<Arc<Stage<T>>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl<T> PipelineBuilder<T>
+where
+    T: Send + 'static,
+{
+    /// Appends a stage delaying items by the provided delay before passing them to the handler.
+    /// A handler returning the item forwards it to the next stage, returning
+    /// [None](core::option::Option::None) completes it. Items returned by the last stage are dropped.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Pipeline;
+    /// let builder = Pipeline::builder().stage(Duration::from_secs(1), |e: u32| Some(e));
+    /// ```
+    pub fn stage(
+        mut self,
+        delay: Duration,
+        handler: impl Fn(T) -> Option<T> + Send + 'static,
+    ) -> Self {
+        self.stages.push((delay, Box::new(handler)));
+        self
+    }
+
+    /// Spawns the threads of all stages as described by the provided configuration, naming them
+    /// 'pipeline-stage-N' with the configured prefix.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{Pipeline, ThreadConfig};
+    /// let pipeline = Pipeline::builder()
+    ///     .stage(Duration::from_secs(1), |e: u32| Some(e))
+    ///     .thread_config(&ThreadConfig::new().name_prefix("billing"))
+    ///     .build();
+    /// ```
+    pub fn thread_config(mut self, thread_config: &ThreadConfig) -> Self {
+        self.thread_config = thread_config.clone();
+        self
+    }
+
+    /// Captures the current [TraceContext](crate::TraceContext) whenever an item is submitted
+    /// and runs every handler of the item within it, so traces continue across all stages.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{Pipeline, TraceContext};
+    /// struct Span;
+    /// impl TraceContext for Span {
+    ///     fn current() -> Self {
+    ///         Span
+    ///     }
+    ///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+    ///         f()
+    ///     }
+    /// }
+    /// let pipeline = Pipeline::builder()
+    ///     .stage(Duration::from_secs(1), |e: u32| Some(e))
+    ///     .trace_context::<Span>()
+    ///     .build();
+    /// ```
+    pub fn trace_context<C: TraceContext>(mut self) -> Self {
+        self.capture = Some(ContextCapture::new::<C>());
+        self
+    }
+
+    /// Creates the pipeline and starts the threads of its stages.
+    ///
+    /// # Panics
+    /// Panics if no stage was added.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Pipeline;
+    /// let pipeline = Pipeline::builder()
+    ///     .stage(Duration::from_secs(1), |e: u32| Some(e))
+    ///     .build();
+    /// ```
+    pub fn build(self) -> Pipeline<T> {
+        let PipelineBuilder {
+            stages: handlers,
+            thread_config,
+            capture,
+        } = self;
+        assert!(!handlers.is_empty(), "Pipeline requires at least one stage");
+        let stages: Vec<_> = handlers
+            .iter()
+            .map(|(delay, _)| {
+                Arc::new(Stage {
+                    queue: Arc::new(BlockingDelayQueue::new_unbounded()),
+                    delay: *delay,
+                    capture: capture.clone(),
+                })
+            })
+            .collect();
+        let handles = handlers
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_, handler))| {
+                let (stage, next) = (stages[i].clone(), stages.get(i + 1).cloned());
+                let name = format!("pipeline-stage-{}", i);
+                thread_config.spawn(&name, move || {
+                    Pipeline::run(&stage, next.as_deref(), handler)
+                })
+            })
+            .collect();
+
+        Pipeline { stages, handles }
+    }
+}
+
+impl<T> Pipeline<T>
+where
+    T: Send + 'static,
+{
+    /// Creates a builder for a pipeline without stages.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{Pipeline, PipelineBuilder};
+    /// let builder: PipelineBuilder<u32> = Pipeline::builder();
+    /// ```
+    pub fn builder() -> PipelineBuilder<T> {
+        PipelineBuilder {
+            stages: Vec::new(),
+            thread_config: ThreadConfig::new(),
+            capture: None,
+        }
+    }
+
+    /// Submits an item to the first stage, which handles it once the stage's delay has expired.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Pipeline;
+    /// let pipeline = Pipeline::builder()
+    ///     .stage(Duration::from_secs(1), |e: u32| Some(e))
+    ///     .build();
+    /// pipeline.submit(1);
+    /// ```
+    pub fn submit(&self, e: T) {
+        self.stages[0].schedule(e);
+    }
+
+    /// Returns the number of items waiting in all stages.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Pipeline;
+    /// let pipeline = Pipeline::builder()
+    ///     .stage(Duration::from_secs(1), |e: u32| Some(e))
+    ///     .build();
+    /// pipeline.submit(1);
+    /// assert_eq!(1, pipeline.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.stages.iter().map(|s| s.queue.size()).sum()
+    }
+
+    fn run(stage: &Stage<T>, next: Option<&Stage<T>>, handler: Handler<T>) {
+        while let Signal::Item(e, context) = stage.queue.take().data {
+            // the next stage captures the context again while it's entered
+            let handle = || {
+                if let (Some(e), Some(next)) = (handler(e), next) {
+                    next.schedule(e);
+                }
+            };
+            match context {
+                Some(context) => context.in_scope(handle),
+                None => handle(),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Pipeline<T> {
+    fn drop(&mut self) {
+        // stop stages in order, so a stage never hands items to an already stopped one
+        for (stage, handle) in self.stages.iter().zip(self.handles.drain(..)) {
+            stage
+                .queue
+                .add(DelayItem::new(Signal::Stop, Instant::now()));
+            if handle.thread().id() != thread::current().id() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    use crate::pipeline::Pipeline;
+    use crate::trace_context::TraceContext;
+
+    thread_local!(static REQUEST: Cell<u64> = const { Cell::new(0) });
+
+    struct RequestId(u64);
+
+    impl TraceContext for RequestId {
+        fn current() -> Self {
+            RequestId(REQUEST.with(Cell::get))
+        }
+
+        fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+            let outer = REQUEST.with(|r| r.replace(self.0));
+            let r = f();
+            REQUEST.with(|r| r.set(outer));
+            r
+        }
+    }
+
+    #[test]
+    fn should_pass_items_through_delayed_stages() {
+        let (tx, rx) = mpsc::channel();
+        let escalated = tx.clone();
+        let pipeline = Pipeline::builder()
+            .stage(Duration::from_millis(20), move |e: u32| {
+                tx.send(("remind", e)).unwrap();
+                // even items are resolved by the reminder
+                Some(e).filter(|e| e % 2 == 1)
+            })
+            .stage(Duration::from_millis(30), move |e| {
+                escalated.send(("escalate", e)).unwrap();
+                None
+            })
+            .build();
+        let start = Instant::now();
+        pipeline.submit(1);
+        pipeline.submit(2);
+
+        let received: Vec<_> = rx.iter().take(3).collect();
+        assert_eq!(
+            vec![("remind", 1), ("remind", 2), ("escalate", 1)],
+            received
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(0, pipeline.size());
+    }
+
+    #[test]
+    fn should_handle_items_within_context_captured_at_submission() {
+        let (tx, rx) = mpsc::channel();
+        let escalated = tx.clone();
+        let pipeline = Pipeline::builder()
+            .stage(Duration::from_millis(5), move |e: u64| {
+                tx.send((e, REQUEST.with(Cell::get))).unwrap();
+                Some(e)
+            })
+            .stage(Duration::from_millis(5), move |e| {
+                escalated.send((e, REQUEST.with(Cell::get))).unwrap();
+                None
+            })
+            .trace_context::<RequestId>()
+            .build();
+        for request in 1..=2 {
+            REQUEST.with(|r| r.set(request));
+            pipeline.submit(request);
+        }
+        REQUEST.with(|r| r.set(0));
+
+        let mut received: Vec<_> = rx.iter().take(4).collect();
+        received.sort_unstable();
+        assert_eq!(vec![(1, 1), (1, 1), (2, 2), (2, 2)], received);
+    }
+
+    #[test]
+    fn should_discard_pending_items_on_drop() {
+        let (tx, rx) = mpsc::channel();
+        let pipeline = Pipeline::builder()
+            .stage(Duration::from_secs(60), move |e: u32| {
+                tx.send(e).unwrap();
+                None
+            })
+            .build();
+        pipeline.submit(1);
+        drop(pipeline);
+        assert!(rx.recv().is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+use crate::thread_config::ThreadConfig;
+
+// how long a pump waits before retrying to forward into a full bounded channel
+const RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+enum Target<T> {
+    Unbounded(Sender<T>),
+    Bounded(SyncSender<T>),
+}
+
+impl<T> Target<T> {
+    fn try_send(&self, e: T) -> Result<(), TrySendError<T>> {
+        match self {
+            Target::Unbounded(sender) => {
+                sender.send(e).map_err(|e| TrySendError::Disconnected(e.0))
+            }
+            Target::Bounded(sender) => sender.try_send(e),
+        }
+    }
+}
+
+/// A handle to a thread forwarding expired elements of a queue into a std channel, see
+/// [pump_into](crate::BlockingDelayQueue::pump_into).
+/// Dropping the handle stops the thread and waits for it to finish.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::{mpsc, Arc};
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// let queue = Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded());
+/// let (tx, rx) = mpsc::channel();
+/// let pump = queue.pump_into(tx);
+/// assert!(pump.is_running());
+/// pump.stop();
+/// ```
+pub struct PumpHandle {
+    running: Arc<AtomicBool>,
+    wake: Box<dyn Fn() + Send + Sync>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<T> BlockingDelayQueue<T>
+where
+    T: Delayed + Ord + Send + 'static,
+{
+    /// Starts a thread forwarding every expired element of this queue into the provided channel,
+    /// for consumers which are already built around std channels.
+    /// The thread stops when the returned handle is stopped or dropped, or once the receiver
+    /// hangs up. An element only leaves the queue, and its delivery is only reported to listeners,
+    /// once the channel accepted it, so an element which couldn't be forwarded stays in the queue
+    /// in its original order.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::{mpsc, Arc};
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// let (tx, rx) = mpsc::channel();
+    /// let _pump = queue.pump_into(tx);
+    /// queue.add(DelayItem::new("reminder", Instant::now()));
+    /// assert_eq!("reminder", rx.recv().unwrap().data);
+    /// ```
+    pub fn pump_into(self: &Arc<Self>, sender: Sender<T>) -> PumpHandle {
+        self.start_pump(Target::Unbounded(sender), &ThreadConfig::new())
+    }
+
+    /// Starts a pump like ['pump_into'] whose thread is spawned as described by the provided
+    /// configuration. Without a configured name prefix, the thread is named after the queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::{mpsc, Arc};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, ThreadConfig};
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded());
+    /// let (tx, rx) = mpsc::channel();
+    /// let cfg = ThreadConfig::new().name_prefix("billing");
+    /// let pump = queue.pump_into_with_thread_config(tx, &cfg);
+    /// ```
+    pub fn pump_into_with_thread_config(
+        self: &Arc<Self>,
+        sender: Sender<T>,
+        thread_config: &ThreadConfig,
+    ) -> PumpHandle {
+        self.start_pump(Target::Unbounded(sender), thread_config)
+    }
+
+    /// Starts a thread forwarding every expired element of this queue into the provided bounded
+    /// channel like ['pump_into'].
+    /// While the channel is full, expired elements stay in the queue, so a slow receiver applies
+    /// backpressure instead of buffering without bound.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::{mpsc, Arc};
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+    /// let (tx, rx) = mpsc::sync_channel(16);
+    /// let _pump = queue.pump_into_sync(tx);
+    /// queue.add(DelayItem::new("reminder", Instant::now()));
+    /// assert_eq!("reminder", rx.recv().unwrap().data);
+    /// ```
+    pub fn pump_into_sync(self: &Arc<Self>, sender: SyncSender<T>) -> PumpHandle {
+        self.start_pump(Target::Bounded(sender), &ThreadConfig::new())
+    }
+
+    /// Starts a pump like ['pump_into_sync'] whose thread is spawned as described by the provided
+    /// configuration. Without a configured name prefix, the thread is named after the queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::{mpsc, Arc};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, ThreadConfig};
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded());
+    /// let (tx, rx) = mpsc::sync_channel(16);
+    /// let cfg = ThreadConfig::new().stack_size(256 * 1024);
+    /// let pump = queue.pump_into_sync_with_thread_config(tx, &cfg);
+    /// ```
+    pub fn pump_into_sync_with_thread_config(
+        self: &Arc<Self>,
+        sender: SyncSender<T>,
+        thread_config: &ThreadConfig,
+    ) -> PumpHandle {
+        self.start_pump(Target::Bounded(sender), thread_config)
+    }
+
+    fn start_pump(self: &Arc<Self>, target: Target<T>, thread_config: &ThreadConfig) -> PumpHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let (queue, thread_running) = (self.clone(), running.clone());
+        let thread_config = match (&thread_config.name_prefix, self.name()) {
+            (None, Some(name)) => thread_config.clone().name_prefix(name),
+            _ => thread_config.clone(),
+        };
+        let handle = thread_config.spawn("pump", move || {
+            Self::pump(&queue, &target, &thread_running);
+            thread_running.store(false, Ordering::Release);
+        });
+        let queue = self.clone();
+
+        PumpHandle {
+            running,
+            wake: Box::new(move || queue.wake_consumers()),
+            handle: Some(handle),
+        }
+    }
+
+    fn pump(queue: &BlockingDelayQueue<T>, target: &Target<T>, running: &AtomicBool) {
+        // refused elements stay in the queue, so nothing needs to be added back when stopping
+        while let Some(forwarded) = queue.forward_while(running, |e| target.try_send(e)) {
+            match forwarded {
+                Ok(()) => {}
+                Err(TrySendError::Full(())) => thread::sleep(RETRY_INTERVAL),
+                Err(TrySendError::Disconnected(())) => return,
+            }
+        }
+    }
+}
+
+impl PumpHandle {
+    /// Returns 'true' while the pump is forwarding elements, 'false' once it was stopped or the
+    /// receiver hung up.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::{mpsc, Arc};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded());
+    /// let (tx, _rx) = mpsc::channel();
+    /// let pump = queue.pump_into(tx);
+    /// assert!(pump.is_running());
+    /// ```
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Stops the pump and waits for its thread to finish.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::{mpsc, Arc};
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded());
+    /// let (tx, _rx) = mpsc::channel();
+    /// queue.pump_into(tx).stop();
+    /// ```
+    pub fn stop(self) {
+        drop(self)
+    }
+}
+
+impl Drop for PumpHandle {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        (self.wake)();
+        if let Some(handle) = self.handle.take() {
+            if handle.thread().id() != thread::current().id() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::listener::Outcome;
+    use crate::thread_config::ThreadConfig;
+
+    #[test]
+    fn should_forward_expired_items_in_order() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let (tx, rx) = mpsc::channel();
+        let pump = queue.pump_into(tx);
+        queue.add(DelayItem::new(
+            2,
+            Instant::now() + Duration::from_millis(20),
+        ));
+        queue.add(DelayItem::new(1, Instant::now()));
+
+        assert_eq!(1, rx.recv().unwrap().data);
+        assert_eq!(2, rx.recv().unwrap().data);
+        pump.stop();
+        queue.add(DelayItem::new(3, Instant::now()));
+        assert_eq!(1, queue.size());
+    }
+
+    #[test]
+    fn should_apply_backpressure_and_keep_unsent_items_in_queue() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let (delivered_tx, delivered_rx) = mpsc::channel();
+        for i in 0..3 {
+            let delivered_tx = delivered_tx.clone();
+            queue.add_with_listener(DelayItem::new(i, Instant::now()), move |o| {
+                delivered_tx.send((i, o)).unwrap()
+            });
+        }
+        let (tx, rx) = mpsc::sync_channel(1);
+        let pump = queue.pump_into_sync(tx);
+        thread::sleep(Duration::from_millis(20));
+        // one item in the channel, the others still in the queue
+        assert_eq!(2, queue.size());
+        assert_eq!((0, Outcome::Delivered), delivered_rx.try_recv().unwrap());
+        assert!(delivered_rx.try_recv().is_err());
+
+        pump.stop();
+        assert_eq!(0, rx.recv().unwrap().data);
+        assert_eq!(1, queue.take().data);
+        assert_eq!(2, queue.take().data);
+    }
+
+    #[test]
+    fn should_spawn_pump_thread_as_configured() {
+        let (started_tx, started_rx) = mpsc::channel();
+        let cfg = ThreadConfig::new().on_start(move || {
+            let name = thread::current().name().map(String::from);
+            started_tx.send(name).unwrap();
+        });
+        let queue = Arc::new(BlockingDelayQueue::builder().name("orders").build());
+        let (tx, rx) = mpsc::sync_channel(1);
+        let _pump = queue.pump_into_sync_with_thread_config(tx, &cfg);
+        queue.add(DelayItem::new(1, Instant::now()));
+
+        assert_eq!(1, rx.recv().unwrap().data);
+        assert_eq!(Some("orders-pump".to_string()), started_rx.recv().unwrap());
+        let billing = cfg.name_prefix("billing");
+        let (tx, _rx) = mpsc::channel();
+        let _pump = queue.pump_into_with_thread_config(tx, &billing);
+        assert_eq!(Some("billing-pump".to_string()), started_rx.recv().unwrap());
+    }
+
+    #[test]
+    fn should_stop_when_receiver_hangs_up() {
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        let (tx, rx) = mpsc::channel();
+        let pump = queue.pump_into(tx);
+        drop(rx);
+        queue.add(DelayItem::new(1, Instant::now()));
+
+        let start = Instant::now();
+        while pump.is_running() && start.elapsed() < Duration::from_secs(1) {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!pump.is_running());
+        assert_eq!(1, queue.take().data);
+    }
+}
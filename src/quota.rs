@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+/// An error returned when an element is rejected because its tenant already reached the
+/// configured number of pending elements, see [quota](crate::QueueBuilder::quota).
+/// The rejected element is handed back to the caller.
+pub struct QuotaExceeded<T>(pub T);
+
+impl<T> fmt::Debug for QuotaExceeded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuotaExceeded").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for QuotaExceeded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tenant quota of pending elements exceeded")
+    }
+}
+
+impl<T> Error for QuotaExceeded<T> {}
+
+// tracks pending elements per tenant under the queue lock
+pub(crate) trait Quota<T> {
+    fn allows(&self, e: &T) -> bool;
+    fn acquire(&mut self, e: &T);
+    fn release(&mut self, e: &T);
+}
+
+pub(crate) struct KeyedQuota<T, K> {
+    key: Box<dyn Fn(&T) -> K + Send>,
+    max_pending: usize,
+    pending: HashMap<K, usize>,
+}
+
+impl<T, K> KeyedQuota<T, K> {
+    pub(crate) fn new(key: impl Fn(&T) -> K + Send + 'static, max_pending: usize) -> Self {
+        KeyedQuota {
+            key: Box::new(key),
+            max_pending,
+            pending: HashMap::new(),
+        }
+    }
+}
+
+impl<T, K> Quota<T> for KeyedQuota<T, K>
+where
+    K: Eq + Hash,
+{
+    fn allows(&self, e: &T) -> bool {
+        self.pending.get(&(self.key)(e)).copied().unwrap_or(0) < self.max_pending
+    }
+
+    fn acquire(&mut self, e: &T) {
+        *self.pending.entry((self.key)(e)).or_insert(0) += 1;
+    }
+
+    fn release(&mut self, e: &T) {
+        let key = (self.key)(e);
+        if let Some(count) = self.pending.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                self.pending.remove(&key);
+            }
+        }
+    }
+}
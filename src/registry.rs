@@ -0,0 +1,163 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+
+type Entries = HashMap<String, Arc<dyn Any + Send + Sync>>;
+
+static GLOBAL: OnceLock<QueueRegistry> = OnceLock::new();
+
+/// A registry of named delay queues, allowing different parts of a program to share queues
+/// by name instead of passing handles around.
+/// A process-wide instance is available through [global](QueueRegistry::global).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Instant;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, QueueRegistry};
+/// let registry = QueueRegistry::new();
+/// registry.register("emails", Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded()));
+/// let emails = registry.get::<DelayItem<&str>>("emails").unwrap();
+/// emails.add(DelayItem::new("hello", Instant::now()));
+/// ```
+pub struct QueueRegistry {
+    entries: Mutex<Entries>,
+}
+
+impl Default for QueueRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QueueRegistry {
+    /// Creates a new empty registry.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::QueueRegistry;
+    /// let registry = QueueRegistry::new();
+    /// ```
+    pub fn new() -> Self {
+        QueueRegistry {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the process-wide registry.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, QueueRegistry};
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<u64>>::new_unbounded());
+    /// QueueRegistry::global().register("jobs", queue);
+    /// assert!(QueueRegistry::global().get::<DelayItem<u64>>("jobs").is_some());
+    /// ```
+    pub fn global() -> &'static QueueRegistry {
+        GLOBAL.get_or_init(QueueRegistry::new)
+    }
+
+    /// Registers a queue under the provided name.
+    /// Returns 'false' without replacing anything if the name is already taken, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, QueueRegistry};
+    /// let registry = QueueRegistry::new();
+    /// let queue = Arc::new(BlockingDelayQueue::<DelayItem<&str>>::new_unbounded());
+    /// assert!(registry.register("emails", queue.clone()));
+    /// assert!(!registry.register("emails", queue));
+    /// ```
+    pub fn register<T>(&self, name: &str, queue: Arc<BlockingDelayQueue<T>>) -> bool
+    where
+        T: Send + 'static,
+    {
+        let mut entries = self.entries_mutex();
+        if entries.contains_key(name) {
+            false
+        } else {
+            entries.insert(name.to_string(), queue);
+            true
+        }
+    }
+
+    /// Returns the queue registered under the provided name.
+    /// Returns [None](core::option::Option::None) if there is no such queue or its element type
+    /// doesn't match.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, QueueRegistry};
+    /// let registry = QueueRegistry::new();
+    /// assert!(registry.get::<DelayItem<&str>>("emails").is_none());
+    /// ```
+    pub fn get<T>(&self, name: &str) -> Option<Arc<BlockingDelayQueue<T>>>
+    where
+        T: Send + 'static,
+    {
+        let entry = self.entries_mutex().get(name).cloned();
+        entry.and_then(|e| e.downcast::<BlockingDelayQueue<T>>().ok())
+    }
+
+    /// Removes the queue registered under the provided name.
+    /// Returns 'true' if a queue was removed.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::QueueRegistry;
+    /// let registry = QueueRegistry::new();
+    /// assert!(!registry.unregister("emails"));
+    /// ```
+    pub fn unregister(&self, name: &str) -> bool {
+        self.entries_mutex().remove(name).is_some()
+    }
+
+    fn entries_mutex(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().expect("Registry lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::registry::QueueRegistry;
+
+    #[test]
+    fn should_share_registered_queue() {
+        let registry = QueueRegistry::new();
+        let queue = Arc::new(BlockingDelayQueue::new_unbounded());
+        assert!(registry.register("q", queue.clone()));
+
+        queue.add(DelayItem::new(1, Instant::now()));
+
+        let shared = registry.get::<DelayItem<i32>>("q").unwrap();
+        assert_eq!(1, shared.take().data);
+        assert!(registry.unregister("q"));
+        assert!(registry.get::<DelayItem<i32>>("q").is_none());
+    }
+
+    #[test]
+    fn should_not_get_queue_of_other_type() {
+        let registry = QueueRegistry::new();
+        registry.register(
+            "q",
+            Arc::new(BlockingDelayQueue::<DelayItem<i32>>::new_unbounded()),
+        );
+
+        assert!(registry.get::<DelayItem<String>>("q").is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+
+type Classifier<T, R> = Box<dyn Fn(&T) -> R + Send + Sync>;
+
+/// A dispatcher placing each added element into one of the registered delay queues, selected
+/// by applying a classifier function to the element.
+/// Routes can be added and removed at runtime while consumers attach to a route's queue.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Instant;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, Router};
+/// let router = Router::new(|e: &DelayItem<i32>| e.data % 2 == 0);
+/// router.add_route(true, Arc::new(BlockingDelayQueue::new_unbounded()));
+/// router.add_route(false, Arc::new(BlockingDelayQueue::new_unbounded()));
+/// assert!(router.add(DelayItem::new(2, Instant::now())).is_ok());
+/// let even = router.route(&true).unwrap();
+/// assert_eq!(2, even.take().data);
+/// ```
+pub struct Router<T, R> {
+    classifier: Classifier<T, R>,
+    routes: Mutex<HashMap<R, Arc<BlockingDelayQueue<T>>>>,
+}
+
+impl<T, R> Router<T, R>
+where
+    T: Delayed + Ord,
+    R: Eq + Hash,
+{
+    /// Creates a new router without routes using the provided classifier.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, Router};
+    /// let router = Router::new(|e: &DelayItem<&str>| e.data.len());
+    /// ```
+    pub fn new(classifier: impl Fn(&T) -> R + Send + Sync + 'static) -> Self {
+        Router {
+            classifier: Box::new(classifier),
+            routes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a queue for the provided route returning the previously registered queue if any.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, Router};
+    /// let router = Router::new(|e: &DelayItem<&str>| e.data.len());
+    /// router.add_route(1, Arc::new(BlockingDelayQueue::new_unbounded()));
+    /// ```
+    pub fn add_route(
+        &self,
+        route: R,
+        queue: Arc<BlockingDelayQueue<T>>,
+    ) -> Option<Arc<BlockingDelayQueue<T>>> {
+        self.routes_mutex().insert(route, queue)
+    }
+
+    /// Removes the queue registered for the provided route.
+    /// Elements already placed into the removed queue stay there.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::sync::Arc;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, Router};
+    /// let router = Router::new(|e: &DelayItem<&str>| e.data.len());
+    /// router.add_route(1, Arc::new(BlockingDelayQueue::new_unbounded()));
+    /// assert!(router.remove_route(&1).is_some());
+    /// ```
+    pub fn remove_route(&self, route: &R) -> Option<Arc<BlockingDelayQueue<T>>> {
+        self.routes_mutex().remove(route)
+    }
+
+    /// Returns the queue registered for the provided route, used by consumers to attach to it.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{DelayItem, Router};
+    /// let router = Router::new(|e: &DelayItem<&str>| e.data.len());
+    /// assert!(router.route(&1).is_none());
+    /// ```
+    pub fn route(&self, route: &R) -> Option<Arc<BlockingDelayQueue<T>>> {
+        self.routes_mutex().get(route).cloned()
+    }
+
+    /// Adds an element to the queue of its route waiting if necessary until space becomes available.
+    /// Returns the element back as an error when there is no queue registered for its route.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, Router};
+    /// let router = Router::new(|e: &DelayItem<&str>| e.data.len());
+    /// let res = router.add(DelayItem::new("unrouted", Instant::now()));
+    /// assert!(res.is_err());
+    /// ```
+    pub fn add(&self, e: T) -> Result<(), T> {
+        let route = (self.classifier)(&e);
+        // release the routes lock before a potentially blocking add
+        let queue = self.route(&route);
+        match queue {
+            Some(q) => {
+                q.add(e);
+                Ok(())
+            }
+            _ => Err(e),
+        }
+    }
+
+    fn routes_mutex(&self) -> MutexGuard<'_, HashMap<R, Arc<BlockingDelayQueue<T>>>> {
+        self.routes.lock().expect("Router lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::router::Router;
+
+    #[test]
+    fn should_route_by_classifier() {
+        let router = Router::new(|e: &DelayItem<i32>| e.data > 10);
+        let big = Arc::new(BlockingDelayQueue::new_unbounded());
+        let small = Arc::new(BlockingDelayQueue::new_unbounded());
+        router.add_route(true, big.clone());
+        router.add_route(false, small.clone());
+
+        assert!(router.add(DelayItem::new(100, Instant::now())).is_ok());
+        assert!(router.add(DelayItem::new(1, Instant::now())).is_ok());
+
+        assert_eq!(100, big.take().data);
+        assert_eq!(1, small.take().data);
+        assert_eq!(0, big.size());
+        assert_eq!(0, small.size());
+    }
+
+    #[test]
+    fn should_reject_item_after_route_removed() {
+        let router = Router::new(|_: &DelayItem<i32>| "all");
+        router.add_route("all", Arc::new(BlockingDelayQueue::new_unbounded()));
+        router.remove_route(&"all");
+
+        let res = router.add(DelayItem::new(1, Instant::now()));
+        assert_eq!(1, res.err().unwrap().data);
+    }
+}
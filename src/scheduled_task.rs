@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+use std::time::Instant;
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+
+/// A task which runs once its delay has expired, so tasks of different types can share one
+/// [TaskQueue](crate::TaskQueue) as boxed trait objects without a wrapper enum.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Instant;
+/// use blocking_delay_queue::{Delayed, ScheduledTask};
+/// struct Cleanup {
+///     at: Instant,
+/// }
+/// impl Delayed for Cleanup {
+///     fn delay(&self) -> Instant {
+///         self.at
+///     }
+/// }
+/// impl ScheduledTask for Cleanup {
+///     fn run(&mut self) {
+///         println!("cleaning up");
+///     }
+/// }
+/// ```
+pub trait ScheduledTask: Delayed + Send {
+    /// Runs the task.
+    fn run(&mut self);
+}
+
+/// A delay queue of boxed tasks of any type, ordered by their delay.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Instant;
+/// use blocking_delay_queue::{Delayed, ScheduledTask, TaskQueue};
+/// struct Print(&'static str, Instant);
+/// impl Delayed for Print {
+///     fn delay(&self) -> Instant {
+///         self.1
+///     }
+/// }
+/// impl ScheduledTask for Print {
+///     fn run(&mut self) {
+///         println!("{}", self.0);
+///     }
+/// }
+/// let queue = TaskQueue::new_unbounded();
+/// queue.add(Box::new(Print("hello", Instant::now())));
+/// queue.run_next();
+/// ```
+pub type TaskQueue = BlockingDelayQueue<Box<dyn ScheduledTask>>;
+
+impl Delayed for Box<dyn ScheduledTask> {
+    fn delay(&self) -> Instant {
+        (**self).delay()
+    }
+}
+
+impl Ord for Box<dyn ScheduledTask> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.delay().cmp(&other.delay())
+    }
+}
+
+impl PartialOrd for Box<dyn ScheduledTask> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Box<dyn ScheduledTask> {
+    fn eq(&self, other: &Self) -> bool {
+        self.delay() == other.delay()
+    }
+}
+
+impl Eq for Box<dyn ScheduledTask> {}
+
+impl BlockingDelayQueue<Box<dyn ScheduledTask>> {
+    /// Takes the next task like ['take'] and runs it on the current thread, without holding the
+    /// queue lock, so the task can schedule further tasks on the same queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{Delayed, ScheduledTask, TaskQueue};
+    /// struct Noop(Instant);
+    /// impl Delayed for Noop {
+    ///     fn delay(&self) -> Instant {
+    ///         self.0
+    ///     }
+    /// }
+    /// impl ScheduledTask for Noop {
+    ///     fn run(&mut self) {}
+    /// }
+    /// let queue = TaskQueue::new_unbounded();
+    /// queue.add(Box::new(Noop(Instant::now())));
+    /// queue.run_next();
+    /// assert_eq!(0, queue.size());
+    /// ```
+    pub fn run_next(&self) {
+        self.take().run();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, Sender};
+    use std::time::{Duration, Instant};
+
+    use crate::delay_item::Delayed;
+    use crate::scheduled_task::{ScheduledTask, TaskQueue};
+
+    struct Email(Instant, Sender<String>);
+
+    struct Retry {
+        at: Instant,
+        attempts: u32,
+        tx: Sender<String>,
+    }
+
+    impl Delayed for Email {
+        fn delay(&self) -> Instant {
+            self.0
+        }
+    }
+
+    impl ScheduledTask for Email {
+        fn run(&mut self) {
+            self.1.send("email".to_string()).unwrap();
+        }
+    }
+
+    impl Delayed for Retry {
+        fn delay(&self) -> Instant {
+            self.at
+        }
+    }
+
+    impl ScheduledTask for Retry {
+        fn run(&mut self) {
+            self.attempts += 1;
+            self.tx.send(format!("retry {}", self.attempts)).unwrap();
+        }
+    }
+
+    #[test]
+    fn should_run_mixed_tasks_by_delay() {
+        let (tx, rx) = mpsc::channel();
+        let queue = TaskQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(Box::new(Email(now + Duration::from_millis(10), tx.clone())));
+        queue.add(Box::new(Retry {
+            at: now,
+            attempts: 0,
+            tx,
+        }));
+
+        queue.run_next();
+        queue.run_next();
+        assert_eq!(vec!["retry 1", "email"], rx.try_iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,184 @@
+use std::hash::Hash;
+use std::time::Duration;
+
+use crate::thread_config::ThreadConfig;
+use crate::watchdog::Watchdog;
+
+/// A manager of idle sessions expiring every session which isn't touched within the idle timeout,
+/// e.g. web sessions or idle connections.
+/// Expired sessions are reported to a callback invoked on a dedicated thread which is stopped
+/// when the manager is dropped. Touching a session only records the time, so sessions touched on
+/// every request don't grow the underlying queue, see [Watchdog](crate::Watchdog).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::mpsc;
+/// use std::time::Duration;
+/// use blocking_delay_queue::SessionManager;
+/// let (tx, rx) = mpsc::channel();
+/// let sessions = SessionManager::new(Duration::from_millis(10), move |id| tx.send(id).unwrap());
+/// sessions.open("session-1");
+/// assert_eq!("session-1", rx.recv().unwrap());
+/// ```
+pub struct SessionManager<K>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+{
+    watchdog: Watchdog<K>,
+    idle_timeout: Duration,
+}
+
+impl<K> SessionManager<K>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+{
+    /// Creates a new session manager expiring sessions idle for longer than the provided timeout.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::SessionManager;
+    /// let sessions = SessionManager::new(Duration::from_secs(1800), |id: u64| println!("{} expired", id));
+    /// ```
+    pub fn new(idle_timeout: Duration, on_expired: impl Fn(K) + Send + 'static) -> Self {
+        Self::new_with_thread_config(idle_timeout, on_expired, &ThreadConfig::new())
+    }
+
+    /// Creates a new session manager whose thread is spawned as described by the provided
+    /// configuration.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{SessionManager, ThreadConfig};
+    /// let cfg = ThreadConfig::new().name_prefix("web");
+    /// let sessions = SessionManager::new_with_thread_config(
+    ///     Duration::from_secs(1800),
+    ///     |id: u64| println!("{} expired", id),
+    ///     &cfg,
+    /// );
+    /// ```
+    pub fn new_with_thread_config(
+        idle_timeout: Duration,
+        on_expired: impl Fn(K) + Send + 'static,
+        thread_config: &ThreadConfig,
+    ) -> Self {
+        SessionManager {
+            watchdog: Watchdog::new_with_thread_config(on_expired, thread_config),
+            idle_timeout,
+        }
+    }
+
+    /// Opens a session, or restarts the idle timeout of an already open one.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::SessionManager;
+    /// let sessions = SessionManager::new(Duration::from_secs(1800), |id: u64| println!("{} expired", id));
+    /// sessions.open(1);
+    /// ```
+    pub fn open(&self, id: K) {
+        self.watchdog.arm(id, self.idle_timeout);
+    }
+
+    /// Slides the expiration of an open session to one idle timeout from now.
+    /// Returns 'false' if the session isn't open because it was never opened, was closed or has
+    /// expired, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::SessionManager;
+    /// let sessions = SessionManager::new(Duration::from_secs(1800), |id: u64| println!("{} expired", id));
+    /// sessions.open(1);
+    /// assert!(sessions.touch(&1));
+    /// assert!(!sessions.touch(&2));
+    /// ```
+    pub fn touch(&self, id: &K) -> bool {
+        self.watchdog.feed(id)
+    }
+
+    /// Closes an open session without reporting it as expired.
+    /// Returns 'false' if the session isn't open, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::SessionManager;
+    /// let sessions = SessionManager::new(Duration::from_secs(1800), |id: u64| println!("{} expired", id));
+    /// sessions.open(1);
+    /// assert!(sessions.close(&1));
+    /// ```
+    pub fn close(&self, id: &K) -> bool {
+        self.watchdog.disarm(id)
+    }
+
+    /// Returns the number of open sessions.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::SessionManager;
+    /// let sessions = SessionManager::new(Duration::from_secs(1800), |id: u64| println!("{} expired", id));
+    /// assert_eq!(0, sessions.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.watchdog.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::session_manager::SessionManager;
+    use crate::thread_config::ThreadConfig;
+
+    #[test]
+    fn should_expire_idle_sessions_only() {
+        let (tx, rx) = mpsc::channel();
+        let sessions =
+            SessionManager::new(Duration::from_millis(60), move |id| tx.send(id).unwrap());
+        sessions.open(1);
+        sessions.open(2);
+        sessions.open(3);
+        assert!(sessions.close(&3));
+
+        for _ in 0..4 {
+            thread::sleep(Duration::from_millis(20));
+            assert!(sessions.touch(&1));
+        }
+        assert_eq!(2, rx.recv_timeout(Duration::from_secs(1)).unwrap());
+        assert!(!sessions.touch(&2));
+        assert_eq!(1, sessions.size());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn should_report_expired_sessions_on_configured_thread() {
+        let (tx, rx) = mpsc::channel();
+        let sessions = SessionManager::new_with_thread_config(
+            Duration::from_millis(10),
+            move |id| {
+                tx.send((id, thread::current().name().map(String::from)))
+                    .unwrap()
+            },
+            &ThreadConfig::new().name_prefix("web"),
+        );
+        sessions.open(1);
+
+        let (id, thread) = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(1, id);
+        assert_eq!(Some("web-watchdog".to_string()), thread);
+    }
+}
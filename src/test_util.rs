@@ -0,0 +1,320 @@
+//! Utilities for testing scheduling logic built on top of [BlockingDelayQueue](crate::BlockingDelayQueue),
+//! available with the `test-util` feature.
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::Delayed;
+
+/// A virtual clock which only moves when it's advanced, for driving a queue built with
+/// [clock](crate::QueueBuilder::clock) deterministically. Clones share the same time.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::test_util::MockClock;
+/// let clock = MockClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(start + Duration::from_secs(60), clock.now());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockClock {
+    /// Creates a new clock starting at the current time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::test_util::MockClock;
+    /// let clock = MockClock::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// Creates a new clock starting at the provided time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::test_util::MockClock;
+    /// let clock = MockClock::starting_at(Instant::now());
+    /// ```
+    pub fn starting_at(now: Instant) -> Self {
+        MockClock {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Returns the current virtual time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::test_util::MockClock;
+    /// let clock = MockClock::new();
+    /// let now = clock.now();
+    /// ```
+    pub fn now(&self) -> Instant {
+        *self.now_mutex()
+    }
+
+    /// Moves the virtual time forward by the provided duration.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::test_util::MockClock;
+    /// let clock = MockClock::new();
+    /// clock.advance(Duration::from_millis(10));
+    /// ```
+    pub fn advance(&self, by: Duration) {
+        *self.now_mutex() += by;
+    }
+
+    fn now_mutex(&self) -> MutexGuard<'_, Instant> {
+        self.now.lock().expect("Mock clock lock poisoned")
+    }
+}
+
+/// A producer adding elements to a queue at scripted virtual times, offsets from the time of
+/// its mock clock when the producer was created.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// use blocking_delay_queue::test_util::{MockClock, ScriptedProducer};
+/// let clock = MockClock::new();
+/// let queue = BlockingDelayQueue::builder().clock(&clock).build();
+/// let start = clock.now();
+/// let mut producer = ScriptedProducer::new(
+///     &clock,
+///     vec![
+///         (Duration::ZERO, DelayItem::new(1, start)),
+///         (Duration::from_millis(10), DelayItem::new(2, start)),
+///     ],
+/// );
+/// assert_eq!(1, producer.advance(&queue, Duration::ZERO));
+/// assert_eq!(1, producer.advance(&queue, Duration::from_millis(10)));
+/// assert_eq!(2, queue.size());
+/// ```
+pub struct ScriptedProducer<T> {
+    clock: MockClock,
+    start: Instant,
+    script: VecDeque<(Duration, T)>,
+}
+
+impl<T> ScriptedProducer<T>
+where
+    T: Delayed + Ord,
+{
+    /// Creates a producer for the provided script of offsets and elements, in any order.
+    /// Elements with the same offset are added in script order.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayItem;
+    /// use blocking_delay_queue::test_util::{MockClock, ScriptedProducer};
+    /// let clock = MockClock::new();
+    /// let producer = ScriptedProducer::new(&clock, vec![(Duration::ZERO, DelayItem::new(1, clock.now()))]);
+    /// ```
+    pub fn new(clock: &MockClock, mut script: Vec<(Duration, T)>) -> Self {
+        script.sort_by_key(|s| s.0);
+        ScriptedProducer {
+            clock: clock.clone(),
+            start: clock.now(),
+            script: script.into(),
+        }
+    }
+
+    /// Advances the mock clock by the provided duration, stopping at the virtual time of every
+    /// scripted element on the way to add it to the queue.
+    /// Returns the number of added elements.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+    /// use blocking_delay_queue::test_util::{MockClock, ScriptedProducer};
+    /// let clock = MockClock::new();
+    /// let queue = BlockingDelayQueue::builder().clock(&clock).build();
+    /// let mut producer = ScriptedProducer::new(&clock, vec![(Duration::from_secs(1), DelayItem::new(1, clock.now()))]);
+    /// assert_eq!(0, producer.advance(&queue, Duration::from_millis(999)));
+    /// assert_eq!(1, producer.advance(&queue, Duration::from_millis(1)));
+    /// ```
+    pub fn advance(&mut self, queue: &BlockingDelayQueue<T>, by: Duration) -> usize {
+        let until = self.clock.now() + by;
+        let mut added = 0;
+        while let Some((offset, _)) = self.script.front() {
+            let at = self.start + *offset;
+            if at > until {
+                break;
+            }
+            self.advance_to(at);
+            let (_, e) = self.script.pop_front().unwrap();
+            queue.add(e);
+            added += 1;
+        }
+        self.advance_to(until);
+        added
+    }
+
+    /// Returns the number of scripted elements which weren't added yet.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::DelayItem;
+    /// use blocking_delay_queue::test_util::{MockClock, ScriptedProducer};
+    /// let clock = MockClock::new();
+    /// let producer = ScriptedProducer::new(&clock, vec![(Duration::ZERO, DelayItem::new(1, clock.now()))]);
+    /// assert_eq!(1, producer.remaining());
+    /// ```
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+
+    fn advance_to(&self, at: Instant) {
+        let now = self.clock.now();
+        if at > now {
+            self.clock.advance(at - now);
+        }
+    }
+}
+
+/// Asserts that elements were delivered in non-decreasing delay order.
+///
+/// # Panics
+/// Panics naming the first element delivered before an element with an earlier delay.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::DelayItem;
+/// use blocking_delay_queue::test_util::assert_delivery_order;
+/// let now = Instant::now();
+/// assert_delivery_order(&[DelayItem::new(1, now), DelayItem::new(2, now + Duration::from_secs(1))]);
+/// ```
+pub fn assert_delivery_order<T>(delivered: &[T])
+where
+    T: Delayed,
+{
+    for (i, pair) in delivered.windows(2).enumerate() {
+        assert!(
+            pair[0].delay() <= pair[1].delay(),
+            "element {} was delivered before element {} with an earlier delay",
+            i,
+            i + 1
+        );
+    }
+}
+
+/// Asserts that an element wasn't delivered before its delay expired nor later than the
+/// tolerated lateness after it.
+///
+/// # Panics
+/// Panics if the element was delivered early or too late.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem};
+/// use blocking_delay_queue::test_util::assert_lateness_within;
+/// let queue = BlockingDelayQueue::new_unbounded();
+/// queue.add(DelayItem::new(1, Instant::now() + Duration::from_millis(5)));
+/// let item = queue.take();
+/// assert_lateness_within(&item, Instant::now(), Duration::from_secs(1));
+/// ```
+pub fn assert_lateness_within<T>(e: &T, delivered_at: Instant, tolerance: Duration)
+where
+    T: Delayed,
+{
+    let delay = e.delay();
+    assert!(
+        delivered_at >= delay,
+        "element was delivered {:?} before its delay expired",
+        delay - delivered_at
+    );
+    let lateness = delivered_at - delay;
+    assert!(
+        lateness <= tolerance,
+        "element was delivered {:?} late, tolerated {:?}",
+        lateness,
+        tolerance
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::test_util::{
+        assert_delivery_order, assert_lateness_within, MockClock, ScriptedProducer,
+    };
+
+    #[test]
+    fn should_deliver_scripted_items_at_virtual_times() {
+        let clock = MockClock::new();
+        let queue = BlockingDelayQueue::builder().clock(&clock).build();
+        let start = clock.now();
+        let mut producer = ScriptedProducer::new(
+            &clock,
+            vec![
+                (
+                    Duration::from_secs(20),
+                    DelayItem::new(2, start + Duration::from_secs(31)),
+                ),
+                (
+                    Duration::ZERO,
+                    DelayItem::new(1, start + Duration::from_secs(10)),
+                ),
+            ],
+        );
+
+        assert_eq!(1, producer.advance(&queue, Duration::from_secs(5)));
+        assert!(queue.poll(Duration::ZERO).is_none());
+        assert_eq!(1, producer.advance(&queue, Duration::from_secs(25)));
+        let mut delivered = vec![queue.poll(Duration::ZERO).unwrap()];
+        assert!(queue.poll(Duration::ZERO).is_none());
+        clock.advance(Duration::from_secs(1));
+        delivered.push(queue.poll(Duration::ZERO).unwrap());
+
+        assert_eq!(0, producer.remaining());
+        assert_lateness_within(&delivered[1], clock.now(), Duration::from_secs(1));
+        assert_delivery_order(&delivered);
+    }
+
+    #[test]
+    #[should_panic(expected = "element 0 was delivered before element 1")]
+    fn should_detect_out_of_order_delivery() {
+        let now = Instant::now();
+        assert_delivery_order(&[
+            DelayItem::new(1, now + Duration::from_secs(1)),
+            DelayItem::new(2, now),
+        ]);
+    }
+}
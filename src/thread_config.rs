@@ -0,0 +1,161 @@
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Options applied to threads spawned by this crate, i.e. the threads of
+/// [Watchdog](crate::Watchdog), [SessionManager](crate::SessionManager),
+/// [DelayedExecutor](crate::DelayedExecutor), [Pipeline](crate::Pipeline) and queue
+/// [pumps](crate::BlockingDelayQueue::pump_into_with_thread_config), so they can be
+/// identified in profilers and scheduled according to deployment requirements.
+/// The executor of the `global` module runs on a thread configured with the 'global' name prefix.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use blocking_delay_queue::{DelayedExecutor, ThreadConfig};
+/// let cfg = ThreadConfig::new()
+///     .name_prefix("billing")
+///     .stack_size(256 * 1024)
+///     .on_start(|| println!("pin the current thread here"))
+///     .on_stop(|| println!("unregister the current thread here"));
+/// let executor = DelayedExecutor::new_with_thread_config(&cfg);
+/// ```
+#[derive(Clone, Default)]
+pub struct ThreadConfig {
+    pub(crate) name_prefix: Option<String>,
+    pub(crate) stack_size: Option<usize>,
+    pub(crate) on_start: Option<Arc<dyn Fn() + Send + Sync>>,
+    pub(crate) on_stop: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+// runs the stop callback when the thread finishes, even if it's unwinding
+struct OnStop(Option<Arc<dyn Fn() + Send + Sync>>);
+
+impl Drop for OnStop {
+    fn drop(&mut self) {
+        if let Some(on_stop) = &self.0 {
+            on_stop();
+        }
+    }
+}
+
+impl ThreadConfig {
+    /// Creates a configuration keeping the default name and stack size.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::ThreadConfig;
+    /// let cfg = ThreadConfig::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefixes the thread name, e.g. 'billing' names the watchdog thread 'billing-watchdog'.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::ThreadConfig;
+    /// let cfg = ThreadConfig::new().name_prefix("billing");
+    /// ```
+    pub fn name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the stack size of the thread in bytes.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::ThreadConfig;
+    /// let cfg = ThreadConfig::new().stack_size(256 * 1024);
+    /// ```
+    pub fn stack_size(mut self, bytes: usize) -> Self {
+        self.stack_size = Some(bytes);
+        self
+    }
+
+    /// Sets a callback invoked on the spawned thread before it starts working, e.g. for pinning
+    /// it to a core or adjusting its scheduling priority.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::ThreadConfig;
+    /// let cfg = ThreadConfig::new().on_start(|| println!("pin the current thread here"));
+    /// ```
+    pub fn on_start(mut self, on_start: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_start = Some(Arc::new(on_start));
+        self
+    }
+
+    /// Sets a callback invoked on the spawned thread right before it finishes, including when it
+    /// finishes because of a panic, e.g. for unregistering it from a profiler or flushing
+    /// thread-local state.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::ThreadConfig;
+    /// let cfg = ThreadConfig::new().on_stop(|| println!("flush thread-local metrics here"));
+    /// ```
+    pub fn on_stop(mut self, on_stop: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_stop = Some(Arc::new(on_stop));
+        self
+    }
+
+    pub(crate) fn spawn<F>(&self, name: &str, f: F) -> JoinHandle<()>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let name = match &self.name_prefix {
+            Some(prefix) => format!("{}-{}", prefix, name),
+            _ => name.to_string(),
+        };
+        let mut builder = thread::Builder::new().name(name);
+        if let Some(bytes) = self.stack_size {
+            builder = builder.stack_size(bytes);
+        }
+        let (on_start, on_stop) = (self.on_start.clone(), OnStop(self.on_stop.clone()));
+        builder
+            .spawn(move || {
+                let _on_stop = on_stop;
+                if let Some(on_start) = on_start {
+                    on_start();
+                }
+                f()
+            })
+            .expect("Failed to spawn thread")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crate::thread_config::ThreadConfig;
+
+    #[test]
+    fn should_spawn_configured_thread() {
+        let (tx, rx) = mpsc::channel();
+        let started = tx.clone();
+        let stopped = tx.clone();
+        let cfg = ThreadConfig::new()
+            .name_prefix("billing")
+            .on_start(move || started.send("started".to_string()).unwrap())
+            .on_stop(move || stopped.send("stopped".to_string()).unwrap());
+        cfg.spawn("worker", move || {
+            let name = thread::current().name().unwrap().to_string();
+            tx.send(name).unwrap();
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!("started", rx.recv().unwrap());
+        assert_eq!("billing-worker", rx.recv().unwrap());
+        assert_eq!("stopped", rx.recv().unwrap());
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+
+/// Defines how a [Ticker](crate::Ticker) catches up with ticks missed because the consumer
+/// didn't wait for them in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedTicks {
+    /// Missed ticks are delivered immediately one after another until the ticker is back on schedule.
+    #[default]
+    Burst,
+    /// Missed ticks are dropped and the next tick is the first one of the original schedule
+    /// which is still in the future.
+    Skip,
+}
+
+/// An interval ticker delivering ticks on a fixed schedule.
+/// Every tick is computed from the original schedule instead of from the time the previous tick
+/// was consumed, so the ticker doesn't drift no matter how long the consumer takes per tick.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::Ticker;
+/// let ticker = Ticker::new(Duration::from_millis(10));
+/// for tick in ticker.take(3) {
+///     println!("tick scheduled at {:?}", tick);
+/// }
+/// ```
+pub struct Ticker {
+    queue: BlockingDelayQueue<DelayItem<()>>,
+    period: Duration,
+    missed_ticks: MissedTicks,
+}
+
+impl Ticker {
+    /// Creates a new ticker whose first tick is one period from now, delivering missed ticks
+    /// in a burst.
+    ///
+    /// # Panics
+    /// Panics if the period is zero.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Ticker;
+    /// let ticker = Ticker::new(Duration::from_secs(1));
+    /// ```
+    pub fn new(period: Duration) -> Self {
+        Self::new_with_missed_ticks(period, MissedTicks::Burst)
+    }
+
+    /// Creates a new ticker whose first tick is one period from now, handling missed ticks
+    /// as provided.
+    ///
+    /// # Panics
+    /// Panics if the period is zero.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::{MissedTicks, Ticker};
+    /// let ticker = Ticker::new_with_missed_ticks(Duration::from_secs(1), MissedTicks::Skip);
+    /// ```
+    pub fn new_with_missed_ticks(period: Duration, missed_ticks: MissedTicks) -> Self {
+        assert!(!period.is_zero(), "Ticker period must not be zero");
+        let queue = BlockingDelayQueue::new_with_capacity(1);
+        queue.add(DelayItem::new((), Instant::now() + period));
+        Ticker {
+            queue,
+            period,
+            missed_ticks,
+        }
+    }
+
+    /// Waits until the next tick is due and returns the time it was scheduled at.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::Ticker;
+    /// let ticker = Ticker::new(Duration::from_millis(10));
+    /// assert!(ticker.wait() <= Instant::now());
+    /// ```
+    pub fn wait(&self) -> Instant {
+        let scheduled = self.queue.take().delay;
+        let mut next = scheduled + self.period;
+        if self.missed_ticks == MissedTicks::Skip {
+            let now = Instant::now();
+            if next <= now {
+                let skipped = now.duration_since(next).as_nanos() / self.period.as_nanos() + 1;
+                next += Duration::from_nanos((skipped * self.period.as_nanos()) as u64);
+            }
+        }
+        self.queue.add(DelayItem::new((), next));
+        scheduled
+    }
+
+    /// Returns the period of this ticker.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Ticker;
+    /// let ticker = Ticker::new(Duration::from_secs(1));
+    /// assert_eq!(Duration::from_secs(1), ticker.period());
+    /// ```
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+impl Iterator for Ticker {
+    type Item = Instant;
+
+    // never ends, every call blocks until the next tick
+    fn next(&mut self) -> Option<Instant> {
+        Some(self.wait())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::ticker::{MissedTicks, Ticker};
+
+    #[test]
+    fn should_tick_on_original_schedule() {
+        let period = Duration::from_millis(10);
+        let ticker = Ticker::new(period);
+        let first = ticker.wait();
+        thread::sleep(Duration::from_millis(25));
+
+        // missed ticks burst out on schedule instead of restarting from the late wait
+        assert_eq!(first + period, ticker.wait());
+        assert_eq!(first + period * 2, ticker.wait());
+        assert_eq!(first + period * 3, ticker.wait());
+    }
+
+    #[test]
+    fn should_skip_missed_ticks() {
+        let period = Duration::from_millis(10);
+        let ticker = Ticker::new_with_missed_ticks(period, MissedTicks::Skip);
+        let first = ticker.wait();
+        thread::sleep(Duration::from_millis(25));
+
+        // the late tick is still delivered, only the ones missed behind it are skipped
+        assert_eq!(first + period, ticker.wait());
+        let next = ticker.wait();
+        assert!(next >= first + period * 3);
+        assert_eq!(0, (next - first).as_nanos() % period.as_nanos());
+    }
+}
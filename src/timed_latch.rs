@@ -0,0 +1,182 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::deadline_wait;
+
+/// A countdown latch letting threads wait, with a deadline, until a number of events happened,
+/// e.g. until all scheduled jobs of a batch reported completion.
+/// Once the count reaches zero the latch stays open.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use std::time::{Duration, Instant};
+/// use blocking_delay_queue::TimedLatch;
+/// let latch = Arc::new(TimedLatch::new(2));
+/// for _ in 0..2 {
+///     let latch = latch.clone();
+///     thread::spawn(move || latch.count_down());
+/// }
+/// assert!(latch.wait_until(Instant::now() + Duration::from_secs(1)));
+/// ```
+pub struct TimedLatch {
+    count: Mutex<usize>,
+    condvar: Condvar,
+    max_wait_slice: Option<Duration>,
+}
+
+impl TimedLatch {
+    /// Creates a new latch which opens after the provided number of ['count_down'] calls.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::TimedLatch;
+    /// let latch = TimedLatch::new(3);
+    /// ```
+    pub fn new(count: usize) -> Self {
+        TimedLatch {
+            count: Mutex::new(count),
+            condvar: Condvar::new(),
+            max_wait_slice: None,
+        }
+    }
+
+    /// Caps every single wait of a waiting thread, like
+    /// [max_wait_slice](crate::QueueBuilder::max_wait_slice), so ['wait_until'] notices an
+    /// expired deadline within one slice even after a system suspend.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimedLatch;
+    /// let latch = TimedLatch::new(3).max_wait_slice(Duration::from_secs(1));
+    /// ```
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// Decrements the count, opening the latch and waking all waiters when it reaches zero.
+    /// Calls on an open latch have no effect.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::TimedLatch;
+    /// let latch = TimedLatch::new(1);
+    /// latch.count_down();
+    /// assert_eq!(0, latch.count());
+    /// ```
+    pub fn count_down(&self) {
+        let mut count = self.count_mutex();
+        if *count > 0 {
+            *count -= 1;
+            if *count == 0 {
+                self.condvar.notify_all();
+            }
+        }
+    }
+
+    /// Waits until the latch opens or the deadline expires, whichever comes first.
+    /// Returns 'true' if the latch is open, 'false' if the deadline expired before.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use blocking_delay_queue::TimedLatch;
+    /// let latch = TimedLatch::new(1);
+    /// assert!(!latch.wait_until(Instant::now() + Duration::from_millis(1)));
+    /// ```
+    pub fn wait_until(&self, deadline: Instant) -> bool {
+        let mut count = self.count_mutex();
+        loop {
+            let now = Instant::now();
+            if *count == 0 {
+                return true;
+            }
+            if deadline <= now {
+                return false;
+            }
+            count = deadline_wait::wait_until(
+                &self.condvar,
+                count,
+                Some(deadline),
+                now,
+                self.max_wait_slice,
+            )
+            .expect("Timed latch lock poisoned");
+        }
+    }
+
+    /// Waits until the latch opens.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::TimedLatch;
+    /// let latch = TimedLatch::new(0);
+    /// latch.wait();
+    /// ```
+    pub fn wait(&self) {
+        let mut count = self.count_mutex();
+        while *count > 0 {
+            count = deadline_wait::wait_until(
+                &self.condvar,
+                count,
+                None,
+                Instant::now(),
+                self.max_wait_slice,
+            )
+            .expect("Timed latch lock poisoned");
+        }
+    }
+
+    /// Returns the number of ['count_down'] calls still required to open the latch.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::TimedLatch;
+    /// assert_eq!(3, TimedLatch::new(3).count());
+    /// ```
+    pub fn count(&self) -> usize {
+        *self.count_mutex()
+    }
+
+    fn count_mutex(&self) -> MutexGuard<'_, usize> {
+        self.count.lock().expect("Timed latch lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::timed_latch::TimedLatch;
+
+    #[test]
+    fn should_open_after_count_downs_or_time_out() {
+        let latch = Arc::new(TimedLatch::new(2));
+        latch.count_down();
+        let start = Instant::now();
+        assert!(!latch.wait_until(start + Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let counter = latch.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            counter.count_down();
+        });
+        assert!(latch.wait_until(Instant::now() + Duration::from_secs(1)));
+        handle.join().unwrap();
+        latch.count_down();
+        assert_eq!(0, latch.count());
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+
+enum Countdown {
+    // no deadline when the timeout is too long to represent, so the entry never times out
+    Running(Option<Instant>),
+    // the remaining timeout of a suspended entry
+    Suspended(Duration),
+}
+
+struct Slot<V> {
+    generation: u64,
+    value: V,
+    countdown: Countdown,
+}
+
+struct Pending<K, V> {
+    entries: HashMap<K, Slot<V>>,
+    generation: u64,
+}
+
+/// A map of pending entries, e.g. requests awaiting a response, where every entry is scheduled
+/// to time out unless it's completed first.
+/// Timed out entries are removed from the map and delivered to the consumer calling
+/// ['take'] / ['poll']. Completion and expiration are resolved atomically, so every entry
+/// is either completed or timed out, never both.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::TimeoutMap;
+/// let requests = TimeoutMap::new();
+/// requests.insert(1, "ping", Duration::from_millis(10));
+/// requests.insert(2, "status", Duration::from_millis(10));
+/// assert_eq!(Some("ping"), requests.complete(&1));
+/// assert_eq!((2, "status"), requests.take());
+/// ```
+pub struct TimeoutMap<K, V> {
+    queue: BlockingDelayQueue<DelayItem<(K, u64)>>,
+    pending: Mutex<Pending<K, V>>,
+}
+
+impl<K, V> Default for TimeoutMap<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> TimeoutMap<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates a new empty timeout map.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::<u64, &str>::new();
+    /// ```
+    pub fn new() -> Self {
+        TimeoutMap {
+            queue: BlockingDelayQueue::new_unbounded(),
+            pending: Mutex::new(Pending {
+                entries: HashMap::new(),
+                generation: 0,
+            }),
+        }
+    }
+
+    /// Inserts a pending entry timing out after the provided timeout, a timeout too long to
+    /// represent, e.g. [Duration::MAX](std::time::Duration::MAX), never times out.
+    /// Returns the value of a replaced pending entry with the same key if any.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::new();
+    /// assert!(requests.insert(1, "ping", Duration::from_secs(1)).is_none());
+    /// ```
+    pub fn insert(&self, key: K, value: V, timeout: Duration) -> Option<V> {
+        let deadline = Instant::now().checked_add(timeout);
+        let (generation, replaced) = {
+            let mut pending = self.pending_mutex();
+            pending.generation += 1;
+            let generation = pending.generation;
+            let slot = Slot {
+                generation,
+                value,
+                countdown: Countdown::Running(deadline),
+            };
+            let replaced = pending.entries.insert(key.clone(), slot);
+            (generation, replaced.map(|e| e.value))
+        };
+        if let Some(deadline) = deadline {
+            self.queue.add(DelayItem::new((key, generation), deadline));
+        }
+        replaced
+    }
+
+    /// Freezes the remaining timeout of a pending entry, so it can't time out until it's resumed.
+    /// Returns 'false' if there is no such entry or it's already suspended, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::new();
+    /// requests.insert(1, "ping", Duration::from_millis(1));
+    /// assert!(requests.suspend(&1));
+    /// assert!(requests.poll(Duration::from_millis(10)).is_none());
+    /// ```
+    pub fn suspend(&self, key: &K) -> bool {
+        let mut pending = self.pending_mutex();
+        pending.generation += 1;
+        let generation = pending.generation;
+        match pending.entries.get_mut(key) {
+            Some(slot) => match slot.countdown {
+                Countdown::Running(deadline) => {
+                    // the new generation turns the scheduled timeout stale
+                    slot.generation = generation;
+                    let remaining = deadline.map_or(Duration::MAX, |d| {
+                        d.saturating_duration_since(Instant::now())
+                    });
+                    slot.countdown = Countdown::Suspended(remaining);
+                    true
+                }
+                Countdown::Suspended(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Resumes the timeout of a suspended entry with the time it had remaining when suspended.
+    /// Returns 'false' if there is no such entry or it isn't suspended, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::new();
+    /// requests.insert(1, "ping", Duration::from_millis(1));
+    /// requests.suspend(&1);
+    /// assert!(requests.resume(&1));
+    /// assert_eq!((1, "ping"), requests.take());
+    /// ```
+    pub fn resume(&self, key: &K) -> bool {
+        let scheduled = {
+            let mut pending = self.pending_mutex();
+            pending.generation += 1;
+            let generation = pending.generation;
+            pending
+                .entries
+                .get_mut(key)
+                .and_then(|slot| match slot.countdown {
+                    Countdown::Suspended(remaining) => {
+                        let deadline = Instant::now().checked_add(remaining);
+                        slot.generation = generation;
+                        slot.countdown = Countdown::Running(deadline);
+                        Some((generation, deadline))
+                    }
+                    Countdown::Running(_) => None,
+                })
+        };
+        match scheduled {
+            Some((generation, deadline)) => {
+                if let Some(deadline) = deadline {
+                    self.queue
+                        .add(DelayItem::new((key.clone(), generation), deadline));
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Completes a pending entry, preventing it from timing out.
+    /// Returns [None](core::option::Option::None) if there is no such entry because it was never
+    /// inserted, was already completed or has already timed out.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::new();
+    /// requests.insert(1, "ping", Duration::from_secs(1));
+    /// assert_eq!(Some("ping"), requests.complete(&1));
+    /// assert_eq!(None, requests.complete(&1));
+    /// ```
+    pub fn complete(&self, key: &K) -> Option<V> {
+        self.pending_mutex().entries.remove(key).map(|e| e.value)
+    }
+
+    /// Retrieves and removes the next timed out entry, waiting if necessary until one is available.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::new();
+    /// requests.insert(1, "ping", Duration::from_millis(1));
+    /// let (key, value) = requests.take();
+    /// ```
+    pub fn take(&self) -> (K, V) {
+        loop {
+            if let Some(e) = self.expire(self.queue.take().data) {
+                return e;
+            }
+        }
+    }
+
+    /// Retrieves and removes the next timed out entry, waiting if necessary until one is
+    /// available or the specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no entry times out within the specified wait time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::<u64, &str>::new();
+    /// assert!(requests.poll(Duration::from_millis(1)).is_none());
+    /// ```
+    pub fn poll(&self, timeout: Duration) -> Option<(K, V)> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let remaining = deadline.map_or(Duration::MAX, |d| {
+                d.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return None;
+            }
+            if let Some(e) = self.expire(self.queue.poll(remaining)?.data) {
+                return Some(e);
+            }
+        }
+    }
+
+    /// Returns the number of pending entries, including suspended ones.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::TimeoutMap;
+    /// let requests = TimeoutMap::<u64, &str>::new();
+    /// assert_eq!(0, requests.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.pending_mutex().entries.len()
+    }
+
+    fn pending_mutex(&self) -> MutexGuard<'_, Pending<K, V>> {
+        self.pending.lock().expect("Timeout map lock poisoned")
+    }
+
+    // resolves a scheduled timeout, skipping completed and replaced entries
+    fn expire(&self, (key, generation): (K, u64)) -> Option<(K, V)> {
+        let mut pending = self.pending_mutex();
+        match pending.entries.get(&key) {
+            Some(e) if e.generation == generation => {
+                pending.entries.remove(&key).map(|e| (key, e.value))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::timeout_map::TimeoutMap;
+
+    #[test]
+    fn should_never_time_out_entries_with_unrepresentable_timeout() {
+        let map = TimeoutMap::new();
+        map.insert(1, "a", Duration::MAX);
+        map.insert(2, "b", Duration::ZERO);
+
+        assert_eq!(Some((2, "b")), map.poll(Duration::MAX));
+        assert!(map.suspend(&1));
+        assert!(map.resume(&1));
+        assert!(map.poll(Duration::from_millis(5)).is_none());
+        assert_eq!(Some("a"), map.complete(&1));
+    }
+
+    #[test]
+    fn should_deliver_timed_out_entries_only() {
+        let map = TimeoutMap::new();
+        map.insert(1, "a", Duration::from_millis(5));
+        map.insert(2, "b", Duration::from_millis(10));
+        map.insert(3, "c", Duration::from_millis(15));
+
+        assert_eq!(Some("b"), map.complete(&2));
+        assert_eq!((1, "a"), map.take());
+        assert_eq!((3, "c"), map.take());
+        assert!(map.poll(Duration::from_millis(20)).is_none());
+        assert_eq!(0, map.size());
+    }
+
+    #[test]
+    fn should_restart_timeout_of_replaced_entry() {
+        let map = TimeoutMap::new();
+        map.insert(1, "a", Duration::from_millis(5));
+        assert_eq!(Some("a"), map.insert(1, "b", Duration::from_millis(50)));
+
+        assert!(map.poll(Duration::from_millis(20)).is_none());
+        assert_eq!(Some((1, "b")), map.poll(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn should_keep_remaining_timeout_while_suspended() {
+        let map = TimeoutMap::new();
+        map.insert(1, "a", Duration::from_millis(30));
+        assert!(map.suspend(&1));
+        assert!(!map.suspend(&1));
+
+        assert!(map.poll(Duration::from_millis(50)).is_none());
+        assert!(map.resume(&1));
+        assert!(!map.resume(&1));
+        let resumed = Instant::now();
+        assert_eq!((1, "a"), map.take());
+        assert!(resumed.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::delay_item::Delayed;
+
+// enters a captured context around the provided function
+type Enter = Box<dyn FnOnce(&mut dyn FnMut()) + Send>;
+
+/// A context, e.g. a tracing span or a request id, captured when work is scheduled and re-entered
+/// when it's delivered, so distributed traces continue across the delay.
+/// Implement it for whichever context the application propagates.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::cell::Cell;
+/// use blocking_delay_queue::TraceContext;
+/// thread_local!(static REQUEST: Cell<u64> = Cell::new(0));
+/// struct RequestId(u64);
+/// impl TraceContext for RequestId {
+///     fn current() -> Self {
+///         RequestId(REQUEST.with(Cell::get))
+///     }
+///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+///         let outer = REQUEST.with(|r| r.replace(self.0));
+///         let r = f();
+///         REQUEST.with(|r| r.set(outer));
+///         r
+///     }
+/// }
+/// ```
+pub trait TraceContext: Send + 'static {
+    /// Captures the context current on the calling thread.
+    fn current() -> Self;
+
+    /// Runs the provided function with this context entered.
+    fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R;
+}
+
+/// An element carrying the [TraceContext](crate::TraceContext) which was current when it was
+/// created. It's ordered and delayed exactly like the wrapped element.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Instant;
+/// use blocking_delay_queue::{BlockingDelayQueue, DelayItem, TraceContext, Traced};
+/// struct Span;
+/// impl TraceContext for Span {
+///     fn current() -> Self {
+///         Span
+///     }
+///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+///         f()
+///     }
+/// }
+/// let queue = BlockingDelayQueue::new_unbounded();
+/// queue.add(Traced::<_, Span>::new(DelayItem::new(123, Instant::now())));
+/// assert_eq!(123, queue.take().in_scope(|e| e.data));
+/// ```
+pub struct Traced<T, C> {
+    item: T,
+    context: C,
+}
+
+// a context captured by a type-erased capture, for components scheduling work within the
+// context of their caller
+pub(crate) struct CapturedContext(Enter);
+
+// captures the current context of a type chosen when the component was created
+#[derive(Clone)]
+pub(crate) struct ContextCapture(Arc<dyn Fn() -> CapturedContext + Send + Sync>);
+
+impl ContextCapture {
+    pub(crate) fn new<C: TraceContext>() -> Self {
+        ContextCapture(Arc::new(|| {
+            let context = C::current();
+            CapturedContext(Box::new(move |f| context.in_scope(f)))
+        }))
+    }
+
+    pub(crate) fn capture(&self) -> CapturedContext {
+        (self.0)()
+    }
+}
+
+impl CapturedContext {
+    pub(crate) fn in_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let (mut f, mut r) = (Some(f), None);
+        (self.0)(&mut || r = f.take().map(|f| f()));
+        r.expect("Trace context to run the function")
+    }
+}
+
+impl<T, C: TraceContext> Traced<T, C> {
+    /// Wraps the provided element, capturing the current context.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, TraceContext, Traced};
+    /// struct Span;
+    /// impl TraceContext for Span {
+    ///     fn current() -> Self {
+    ///         Span
+    ///     }
+    ///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+    ///         f()
+    ///     }
+    /// }
+    /// let traced = Traced::<_, Span>::new(DelayItem::new(123, Instant::now()));
+    /// ```
+    pub fn new(item: T) -> Self {
+        Traced {
+            item,
+            context: C::current(),
+        }
+    }
+
+    /// Returns the captured context.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, TraceContext, Traced};
+    /// struct RequestId(u64);
+    /// impl TraceContext for RequestId {
+    ///     fn current() -> Self {
+    ///         RequestId(7)
+    ///     }
+    ///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+    ///         f()
+    ///     }
+    /// }
+    /// let traced = Traced::<_, RequestId>::new(DelayItem::new(123, Instant::now()));
+    /// assert_eq!(7, traced.context().0);
+    /// ```
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    /// Unwraps the element within the captured context, passing it to the provided function.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::cell::Cell;
+    /// use std::time::Instant;
+    /// use blocking_delay_queue::{DelayItem, TraceContext, Traced};
+    /// thread_local!(static REQUEST: Cell<u64> = Cell::new(0));
+    /// struct RequestId(u64);
+    /// impl TraceContext for RequestId {
+    ///     fn current() -> Self {
+    ///         RequestId(REQUEST.with(Cell::get))
+    ///     }
+    ///     fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+    ///         let outer = REQUEST.with(|r| r.replace(self.0));
+    ///         let r = f();
+    ///         REQUEST.with(|r| r.set(outer));
+    ///         r
+    ///     }
+    /// }
+    /// REQUEST.with(|r| r.set(7));
+    /// let traced = Traced::<_, RequestId>::new(DelayItem::new(123, Instant::now()));
+    /// REQUEST.with(|r| r.set(0));
+    /// assert_eq!(7, traced.in_scope(|_| REQUEST.with(Cell::get)));
+    /// assert_eq!(0, REQUEST.with(Cell::get));
+    /// ```
+    pub fn in_scope<R>(self, f: impl FnOnce(T) -> R) -> R {
+        let Traced { item, context } = self;
+        context.in_scope(|| f(item))
+    }
+}
+
+impl<T: Delayed, C> Delayed for Traced<T, C> {
+    fn delay(&self) -> Instant {
+        self.item.delay()
+    }
+}
+
+impl<T: Ord, C> Ord for Traced<T, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.item.cmp(&other.item)
+    }
+}
+
+impl<T: Ord, C> PartialOrd for Traced<T, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord, C> PartialEq for Traced<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord, C> Eq for Traced<T, C> {}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::blocking_delay_queue::BlockingDelayQueue;
+    use crate::delay_item::DelayItem;
+    use crate::trace_context::{ContextCapture, TraceContext, Traced};
+
+    thread_local!(static REQUEST: Cell<u64> = const { Cell::new(0) });
+
+    struct RequestId(u64);
+
+    impl TraceContext for RequestId {
+        fn current() -> Self {
+            RequestId(REQUEST.with(Cell::get))
+        }
+
+        fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+            let outer = REQUEST.with(|r| r.replace(self.0));
+            let r = f();
+            REQUEST.with(|r| r.set(outer));
+            r
+        }
+    }
+
+    fn traced_in(request: u64, item: DelayItem<u64>) -> Traced<DelayItem<u64>, RequestId> {
+        REQUEST.with(|r| r.set(request));
+        let traced = Traced::new(item);
+        REQUEST.with(|r| r.set(0));
+        traced
+    }
+
+    #[test]
+    fn should_deliver_in_delay_order_within_own_contexts() {
+        let queue = BlockingDelayQueue::new_unbounded();
+        let now = Instant::now();
+        queue.add(traced_in(
+            3,
+            DelayItem::new(3, now + Duration::from_millis(20)),
+        ));
+        queue.add(traced_in(1, DelayItem::new(1, now)));
+        queue.add(traced_in(
+            2,
+            DelayItem::new(2, now + Duration::from_millis(10)),
+        ));
+
+        let delivered: Vec<_> = (0..3)
+            .map(|_| queue.take().in_scope(|e| (e.data, REQUEST.with(Cell::get))))
+            .collect();
+        assert_eq!(vec![(1, 1), (2, 2), (3, 3)], delivered);
+        assert_eq!(0, REQUEST.with(Cell::get));
+    }
+
+    #[test]
+    fn should_restore_outer_context_on_reentry() {
+        let outer = traced_in(1, DelayItem::new(1, Instant::now()));
+        let inner = traced_in(2, DelayItem::new(2, Instant::now()));
+
+        let seen = outer.in_scope(|_| {
+            let nested = inner.in_scope(|_| REQUEST.with(Cell::get));
+            (nested, REQUEST.with(Cell::get))
+        });
+        assert_eq!((2, 1), seen);
+        assert_eq!(0, REQUEST.with(Cell::get));
+    }
+
+    #[test]
+    fn should_enter_captured_context_on_another_thread() {
+        let capture = ContextCapture::new::<RequestId>();
+        REQUEST.with(|r| r.set(5));
+        let captured = capture.capture();
+        REQUEST.with(|r| r.set(0));
+
+        let seen = thread::spawn(move || captured.in_scope(|| REQUEST.with(Cell::get)));
+        assert_eq!(5, seen.join().unwrap());
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::deadline_wait;
+
+/// A blocking queue in which every element expires the same time-to-live after it was pushed,
+/// covering the common "everything expires after X" case without computing deadlines per call.
+/// Since all elements share one time-to-live, insertion order is expiration order and the queue
+/// is a plain FIFO instead of a heap, making both ['push'] and ['take'] O(1).
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::time::Duration;
+/// use blocking_delay_queue::TtlQueue;
+/// let queue = TtlQueue::new(Duration::from_millis(10));
+/// queue.push("first");
+/// queue.push("second");
+/// assert_eq!("first", queue.take());
+/// assert_eq!("second", queue.take());
+/// ```
+pub struct TtlQueue<T> {
+    entries: Mutex<VecDeque<(Instant, T)>>,
+    condvar: Condvar,
+    ttl: Duration,
+    max_wait_slice: Option<Duration>,
+}
+
+impl<T> TtlQueue<T> {
+    /// Creates a new unbounded queue expiring elements after the provided time-to-live.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::<u64>::new(Duration::from_secs(30));
+    /// ```
+    pub fn new(ttl: Duration) -> Self {
+        TtlQueue {
+            entries: Mutex::new(VecDeque::new()),
+            condvar: Condvar::new(),
+            ttl,
+            max_wait_slice: None,
+        }
+    }
+
+    /// Caps every single wait of a blocked consumer, like
+    /// [max_wait_slice](crate::QueueBuilder::max_wait_slice), so expirations are re-checked at
+    /// least once per slice.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::<u64>::new(Duration::from_secs(30)).max_wait_slice(Duration::from_secs(1));
+    /// ```
+    pub fn max_wait_slice(mut self, slice: Duration) -> Self {
+        self.max_wait_slice = Some(slice);
+        self
+    }
+
+    /// Inserts an element which expires one time-to-live from now.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::new(Duration::from_secs(30));
+    /// queue.push(123);
+    /// ```
+    pub fn push(&self, e: T) {
+        let mut entries = self.entries_mutex();
+        // stamped under the lock, so deadlines never decrease along the queue
+        entries.push_back((Instant::now() + self.ttl, e));
+        self.condvar.notify_all();
+    }
+
+    /// Retrieves and removes the head of this queue, waiting if necessary until it has expired.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::new(Duration::from_millis(1));
+    /// queue.push(123);
+    /// assert_eq!(123, queue.take());
+    /// ```
+    pub fn take(&self) -> T {
+        match self.wait_for_element(None) {
+            Some(e) => e,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Retrieves and removes the head of this queue, waiting if necessary until it has expired
+    /// or the specified wait time expires.
+    /// Returns [None](core::option::Option::None) if no element expires within the specified wait time.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::new(Duration::from_secs(30));
+    /// queue.push(123);
+    /// assert!(queue.poll(Duration::from_millis(1)).is_none());
+    /// ```
+    pub fn poll(&self, timeout: Duration) -> Option<T> {
+        self.wait_for_element(Instant::now().checked_add(timeout))
+    }
+
+    /// Returns the number of elements in this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::new(Duration::from_secs(30));
+    /// queue.push(123);
+    /// assert_eq!(1, queue.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.entries_mutex().len()
+    }
+
+    /// Returns the time-to-live of the elements of this queue.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::TtlQueue;
+    /// let queue = TtlQueue::<u64>::new(Duration::from_secs(30));
+    /// assert_eq!(Duration::from_secs(30), queue.ttl());
+    /// ```
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    fn entries_mutex(&self) -> MutexGuard<'_, VecDeque<(Instant, T)>> {
+        self.entries.lock().expect("TTL queue lock poisoned")
+    }
+
+    fn wait_for_element(&self, deadline: Option<Instant>) -> Option<T> {
+        let mut entries = self.entries_mutex();
+        loop {
+            let now = Instant::now();
+            let expires_at = entries.front().map(|e| e.0);
+            if expires_at.is_some_and(|at| at <= now) {
+                return entries.pop_front().map(|e| e.1);
+            }
+            if deadline.is_some_and(|d| d <= now) {
+                return None;
+            }
+
+            let wake_at = match (expires_at, deadline) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            entries = deadline_wait::wait_until(
+                &self.condvar,
+                entries,
+                wake_at,
+                now,
+                self.max_wait_slice,
+            )
+            .expect("TTL queue lock poisoned");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::ttl_queue::TtlQueue;
+
+    #[test]
+    fn should_expire_items_in_insertion_order_after_ttl() {
+        let queue = TtlQueue::new(Duration::from_millis(20));
+        let start = Instant::now();
+        for i in 0..3 {
+            queue.push(i);
+        }
+
+        assert!(queue.poll(Duration::from_millis(5)).is_none());
+        for i in 0..3 {
+            assert_eq!(i, queue.take());
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!(0, queue.size());
+    }
+
+    #[test]
+    fn should_block_take_until_item_is_pushed() {
+        let queue = Arc::new(TtlQueue::new(Duration::from_millis(5)));
+        let consumer = queue.clone();
+        let handle = thread::spawn(move || consumer.take());
+        thread::sleep(Duration::from_millis(10));
+        queue.push("late");
+
+        assert_eq!("late", handle.join().unwrap());
+    }
+}
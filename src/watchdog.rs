@@ -0,0 +1,236 @@
+use std::hash::Hash;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::blocking_delay_queue::BlockingDelayQueue;
+use crate::delay_item::DelayItem;
+use crate::lazy_timeouts::{Check, LazyTimeouts};
+use crate::thread_config::ThreadConfig;
+
+enum Signal<K> {
+    Expire(K, u64),
+    Stop,
+}
+
+struct Shared<K> {
+    queue: BlockingDelayQueue<DelayItem<Signal<K>>>,
+    armed: LazyTimeouts<K>,
+}
+
+/// A heartbeat monitor invoking a callback with the key of every armed entry which wasn't fed
+/// within its timeout.
+/// Feeding a key only records the time; instead of being rescheduled on every heartbeat, a key's
+/// pending check is rescheduled once it fires and finds a recent heartbeat, so frequent heartbeats
+/// don't grow the underlying queue.
+/// Expirations are detected by a dedicated thread which is stopped when the watchdog is dropped.
+///
+/// #Examples
+/// Basic usage:
+/// ```
+/// use std::sync::mpsc;
+/// use std::time::Duration;
+/// use blocking_delay_queue::Watchdog;
+/// let (tx, rx) = mpsc::channel();
+/// let watchdog = Watchdog::new(move |key| tx.send(key).unwrap());
+/// watchdog.arm("worker-1", Duration::from_millis(10));
+/// assert_eq!("worker-1", rx.recv().unwrap());
+/// ```
+pub struct Watchdog<K>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+{
+    shared: Arc<Shared<K>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<K> Watchdog<K>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+{
+    /// Creates a new watchdog invoking the provided callback on its own thread whenever an
+    /// armed key expires.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::Watchdog;
+    /// let watchdog = Watchdog::new(|key: u32| println!("{} missed a heartbeat", key));
+    /// ```
+    pub fn new(on_expired: impl Fn(K) + Send + 'static) -> Self {
+        Self::new_with_thread_config(on_expired, &ThreadConfig::new())
+    }
+
+    /// Creates a new watchdog whose thread is spawned as described by the provided configuration.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::{ThreadConfig, Watchdog};
+    /// let cfg = ThreadConfig::new().name_prefix("sessions");
+    /// let watchdog = Watchdog::new_with_thread_config(|key: u32| println!("{} expired", key), &cfg);
+    /// ```
+    pub fn new_with_thread_config(
+        on_expired: impl Fn(K) + Send + 'static,
+        thread_config: &ThreadConfig,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            queue: BlockingDelayQueue::new_unbounded(),
+            armed: LazyTimeouts::new(),
+        });
+        let thread_shared = shared.clone();
+        let handle = thread_config.spawn("watchdog", move || thread_shared.watch(on_expired));
+
+        Watchdog {
+            shared,
+            handle: Some(handle),
+        }
+    }
+
+    /// Arms the provided key to expire after the timeout unless it's fed in the meantime.
+    /// Arming an already armed key replaces its timeout and restarts it.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Watchdog;
+    /// let watchdog = Watchdog::new(|key: u32| println!("{} missed a heartbeat", key));
+    /// watchdog.arm(1, Duration::from_secs(5));
+    /// ```
+    pub fn arm(&self, key: K, timeout: Duration) {
+        let (generation, at) = self
+            .shared
+            .armed
+            .start(key.clone(), timeout, Instant::now());
+        self.shared.schedule(key, generation, at);
+    }
+
+    /// Restarts the timeout of an armed key.
+    /// Returns 'false' if the key isn't armed, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Watchdog;
+    /// let watchdog = Watchdog::new(|key: u32| println!("{} missed a heartbeat", key));
+    /// watchdog.arm(1, Duration::from_secs(5));
+    /// assert!(watchdog.feed(&1));
+    /// ```
+    pub fn feed(&self, key: &K) -> bool {
+        self.shared.armed.touch(key, Instant::now())
+    }
+
+    /// Stops monitoring the provided key.
+    /// Returns 'false' if the key isn't armed, 'true' otherwise.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use std::time::Duration;
+    /// use blocking_delay_queue::Watchdog;
+    /// let watchdog = Watchdog::new(|key: u32| println!("{} missed a heartbeat", key));
+    /// watchdog.arm(1, Duration::from_secs(5));
+    /// assert!(watchdog.disarm(&1));
+    /// ```
+    pub fn disarm(&self, key: &K) -> bool {
+        self.shared.armed.remove(key)
+    }
+
+    /// Returns the number of armed keys.
+    ///
+    /// #Examples
+    /// Basic usage:
+    /// ```
+    /// use blocking_delay_queue::Watchdog;
+    /// let watchdog = Watchdog::new(|key: u32| println!("{} missed a heartbeat", key));
+    /// assert_eq!(0, watchdog.size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.shared.armed.len()
+    }
+}
+
+impl<K> Drop for Watchdog<K>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+{
+    fn drop(&mut self) {
+        self.shared
+            .queue
+            .add(DelayItem::new(Signal::Stop, Instant::now()));
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<K> Shared<K>
+where
+    K: Eq + Hash,
+{
+    fn schedule(&self, key: K, generation: u64, at: Instant) {
+        self.queue
+            .add(DelayItem::new(Signal::Expire(key, generation), at));
+    }
+
+    fn watch(&self, on_expired: impl Fn(K)) {
+        while let Signal::Expire(key, generation) = self.queue.take().data {
+            // stale signals of re-armed or disarmed keys are skipped
+            match self.armed.check(&key, generation, Instant::now()) {
+                Check::Expired => on_expired(key),
+                Check::Reschedule(at) => self.schedule(key, generation, at),
+                Check::Stale => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::watchdog::Watchdog;
+
+    #[test]
+    fn should_fire_on_missed_heartbeat() {
+        let (tx, rx) = mpsc::channel();
+        let watchdog = Watchdog::new(move |key| tx.send(key).unwrap());
+        watchdog.arm(1, Duration::from_millis(10));
+
+        assert_eq!(1, rx.recv_timeout(Duration::from_secs(1)).unwrap());
+        assert_eq!(0, watchdog.size());
+    }
+
+    #[test]
+    fn should_not_fire_while_fed() {
+        let (tx, rx) = mpsc::channel();
+        let watchdog = Watchdog::new(move |key| tx.send(key).unwrap());
+        watchdog.arm(1, Duration::from_millis(200));
+        for _ in 0..5 {
+            thread::sleep(Duration::from_millis(20));
+            assert!(watchdog.feed(&1));
+        }
+        assert!(rx.try_recv().is_err());
+
+        assert!(watchdog.disarm(&1));
+        assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+    }
+
+    #[test]
+    fn should_not_schedule_on_every_heartbeat() {
+        let (tx, rx) = mpsc::channel();
+        let watchdog = Watchdog::new(move |key| tx.send(key).unwrap());
+        watchdog.arm(1, Duration::from_millis(50));
+        for _ in 0..1000 {
+            assert!(watchdog.feed(&1));
+        }
+        assert_eq!(1, watchdog.shared.queue.size());
+
+        assert_eq!(1, rx.recv_timeout(Duration::from_secs(1)).unwrap());
+        assert_eq!(0, watchdog.size());
+    }
+}